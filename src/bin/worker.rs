@@ -0,0 +1,13 @@
+use std::env;
+
+use ai_world::{distributed::run_worker, xor::XOREnv};
+
+fn main() {
+    let addr = env::args()
+        .nth(1)
+        .expect("Usage: worker <coordinator address>");
+
+    if let Err(err) = run_worker(&addr, &mut XOREnv) {
+        eprintln!("Worker stopped: {}", err);
+    }
+}
@@ -0,0 +1,156 @@
+use rand::{seq::IteratorRandom, seq::SliceRandom, thread_rng};
+
+use crate::{
+    environment::PopulationEnvironment,
+    evaluation::{Evaluation, EvaluationResult},
+    genome::Genome,
+    population::Population,
+};
+
+/// A game between two genomes.
+pub trait Match<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    /// Plays `genome_a` against `genome_b` and returns the score of `genome_a`.
+    fn play(
+        &mut self,
+        genome_a: &Genome<INPUT_SZ, OUTPUT_SZ>,
+        genome_b: &Genome<INPUT_SZ, OUTPUT_SZ>,
+    ) -> f32;
+}
+
+/// Competitive coevolution: every member plays a sample of opponents taken
+/// from the current population and from a hall of fame of past generation
+/// champions. A member's fitness is its average score over those games.
+pub struct Coevolution<const INPUT_SZ: usize, const OUTPUT_SZ: usize, M> {
+    pub game: M,
+    pub population_opponents: usize,
+    pub hall_of_fame_opponents: usize,
+    pub hall_of_fame_capacity: usize,
+    /// Champions of past generations, oldest first.
+    pub hall_of_fame: Vec<Genome<INPUT_SZ, OUTPUT_SZ>>,
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize, M: Match<INPUT_SZ, OUTPUT_SZ>>
+    Coevolution<INPUT_SZ, OUTPUT_SZ, M>
+{
+    pub fn new(
+        game: M,
+        population_opponents: usize,
+        hall_of_fame_opponents: usize,
+        hall_of_fame_capacity: usize,
+    ) -> Self {
+        Self {
+            game,
+            population_opponents,
+            hall_of_fame_opponents,
+            hall_of_fame_capacity,
+            hall_of_fame: Vec::new(),
+        }
+    }
+
+    fn induct(&mut self, champion: Genome<INPUT_SZ, OUTPUT_SZ>) {
+        self.hall_of_fame.push(champion);
+
+        if self.hall_of_fame.len() > self.hall_of_fame_capacity {
+            let excess = self.hall_of_fame.len() - self.hall_of_fame_capacity;
+            self.hall_of_fame.drain(..excess);
+        }
+    }
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize, M: Match<INPUT_SZ, OUTPUT_SZ>>
+    PopulationEnvironment<INPUT_SZ, OUTPUT_SZ> for Coevolution<INPUT_SZ, OUTPUT_SZ, M>
+{
+    fn evaluate_population(
+        &mut self,
+        population: &mut Population<INPUT_SZ, OUTPUT_SZ>,
+    ) -> Evaluation {
+        let mut rng = thread_rng();
+        let members = &population.members;
+
+        let results = members
+            .iter()
+            .enumerate()
+            .map(|(member_id, member)| {
+                let population_opponents = (0..members.len())
+                    .filter(|&opponent_id| opponent_id != member_id)
+                    .choose_multiple(&mut rng, self.population_opponents)
+                    .into_iter()
+                    .map(|opponent_id| &members[opponent_id]);
+                let hall_of_fame_opponents = self
+                    .hall_of_fame
+                    .choose_multiple(&mut rng, self.hall_of_fame_opponents);
+
+                let mut games = 0;
+                let mut score = 0.0;
+
+                for opponent in population_opponents.chain(hall_of_fame_opponents) {
+                    score += self.game.play(member, opponent);
+                    games += 1;
+                }
+
+                let fitness = if games > 0 { score / games as f32 } else { 0.0 };
+
+                (
+                    member_id,
+                    EvaluationResult::new(fitness).with_metric("games", games as f32),
+                )
+            })
+            .collect();
+
+        let evaluation = Evaluation::new(results);
+
+        if !population.members.is_empty() {
+            let champion = population.members[evaluation.champion().0].clone();
+            self.induct(champion);
+        }
+
+        evaluation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{innovation_record::InnovationRecord, lineage::Lineage};
+
+    /// Wins against any opponent with fewer hidden nodes.
+    struct Bigger;
+
+    impl Match<2, 1> for Bigger {
+        fn play(&mut self, genome_a: &Genome<2, 1>, genome_b: &Genome<2, 1>) -> f32 {
+            if genome_a.hidden_nodes > genome_b.hidden_nodes {
+                1.0
+            } else {
+                0.0
+            }
+        }
+    }
+
+    #[test]
+    fn test_hall_of_fame() {
+        let mut rng = thread_rng();
+        let mut population = Population::<2, 1>::new(
+            &mut rng,
+            &mut InnovationRecord::default(),
+            &mut Lineage::default(),
+            10,
+        );
+        population.members[3].hidden_nodes = 1;
+
+        let mut coevolution = Coevolution::new(Bigger, 9, 2, 2);
+
+        let evaluation = coevolution.evaluate_population(&mut population);
+        assert_eq!(evaluation.fitness.len(), 10);
+        assert_eq!(evaluation.champion(), (3, 1.0));
+        assert_eq!(coevolution.hall_of_fame.len(), 1);
+        assert_eq!(coevolution.hall_of_fame[0].hidden_nodes, 1);
+
+        // The champion can no longer beat every opponent once it has to play
+        // a copy of itself from the hall of fame.
+        let evaluation = coevolution.evaluate_population(&mut population);
+        assert_eq!(evaluation.fitness[&3], 0.9);
+
+        coevolution.evaluate_population(&mut population);
+        assert_eq!(coevolution.hall_of_fame.len(), 2);
+    }
+}
@@ -0,0 +1,33 @@
+/// The hyperparameters of a run that can be tuned while it is going.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NeatConfig {
+    /// Chance of an offspring being mutated at all.
+    pub mutation_prob: f64,
+    /// Chances of each kind of mutation, given that an offspring is mutated.
+    pub mutate_weights_rate: f64,
+    pub mutate_new_connection_rate: f64,
+    pub mutate_new_node_rate: f64,
+    /// Chance of mating with a member of another species.
+    pub interspecies_mate_prob: f64,
+    /// Genomes closer than this to a species' representative belong to it.
+    pub compatibility_threshold: f32,
+    /// Fraction of each species that gets to reproduce.
+    pub survival_threshold: f32,
+    /// Generations a species may go without improving before it is removed.
+    pub stagnation_limit: usize,
+}
+
+impl Default for NeatConfig {
+    fn default() -> Self {
+        Self {
+            mutation_prob: 0.2,
+            mutate_weights_rate: 0.90,
+            mutate_new_connection_rate: 0.5,
+            mutate_new_node_rate: 0.2,
+            interspecies_mate_prob: 0.003,
+            compatibility_threshold: 6.0,
+            survival_threshold: 0.2,
+            stagnation_limit: 15,
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::ops::RangeInclusive;
+
+use eframe::egui::{self, emath::Numeric};
+
+use crate::config::NeatConfig;
+
+/// A parameter that was changed in the editor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterChange {
+    /// The first generation evolved with the new value.
+    pub generation: usize,
+    pub name: &'static str,
+    pub value: String,
+}
+
+/// Sliders for the parameters of a run.
+pub struct ConfigEditor<'a> {
+    pub config: &'a mut NeatConfig,
+    pub target_size: &'a mut usize,
+}
+
+impl<'a> ConfigEditor<'a> {
+    /// Returns the name and new value of every parameter that was changed.
+    pub fn show(self, ui: &mut egui::Ui) -> Vec<(&'static str, String)> {
+        let config = self.config;
+        let mut changes = Vec::new();
+
+        egui::Grid::new("config_editor")
+            .num_columns(2)
+            .show(ui, |ui| {
+                let mut slider = |name, value: &mut dyn SliderValue| {
+                    ui.label(name);
+                    if value.slider(ui).changed() {
+                        changes.push((name, value.to_string()));
+                    }
+                    ui.end_row();
+                };
+
+                slider(
+                    "Mutation probability",
+                    &mut (&mut config.mutation_prob, 0.0..=1.0),
+                );
+                slider(
+                    "Weight mutation rate",
+                    &mut (&mut config.mutate_weights_rate, 0.0..=1.0),
+                );
+                slider(
+                    "New connection rate",
+                    &mut (&mut config.mutate_new_connection_rate, 0.0..=1.0),
+                );
+                slider(
+                    "New node rate",
+                    &mut (&mut config.mutate_new_node_rate, 0.0..=1.0),
+                );
+                slider(
+                    "Interspecies mating",
+                    &mut (&mut config.interspecies_mate_prob, 0.0..=0.1),
+                );
+                slider(
+                    "Compatibility threshold",
+                    &mut (&mut config.compatibility_threshold, 0.1..=20.0),
+                );
+                slider(
+                    "Survival threshold",
+                    &mut (&mut config.survival_threshold, 0.0..=1.0),
+                );
+                slider(
+                    "Stagnation limit",
+                    &mut (&mut config.stagnation_limit, 1..=100),
+                );
+                slider("Target size", &mut (&mut *self.target_size, 2..=1000));
+            });
+
+        changes
+    }
+}
+
+/// Lets sliders of different number types share one closure.
+trait SliderValue {
+    fn slider(&mut self, ui: &mut egui::Ui) -> egui::Response;
+
+    fn to_string(&self) -> String;
+}
+
+impl<T: Numeric + ToString> SliderValue for (&mut T, RangeInclusive<T>) {
+    fn slider(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        ui.add(egui::Slider::new(&mut *self.0, self.1.clone()))
+    }
+
+    fn to_string(&self) -> String {
+        self.0.to_string()
+    }
+}
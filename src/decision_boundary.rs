@@ -0,0 +1,236 @@
+use eframe::{
+    egui::{
+        self,
+        plot::{Plot, PlotImage, PlotPoint, Points},
+        ColorImage, TextureHandle, TextureOptions,
+    },
+    epaint::{Color32, Vec2},
+};
+
+use crate::{environment::TestCase, genome::Genome};
+
+const LOW_OUTPUT_COLOR: Color32 = Color32::from_rgb(40, 50, 140);
+const HIGH_OUTPUT_COLOR: Color32 = Color32::from_rgb(250, 210, 80);
+const MIN_RESOLUTION: usize = 4;
+const MAX_RESOLUTION: usize = 128;
+const PLOT_HEIGHT: f32 = 200.0;
+const POINT_RADIUS: f32 = 5.0;
+const POINT_OUTLINE: f32 = 1.5;
+
+/// Blends from [`LOW_OUTPUT_COLOR`] at 0 to [`HIGH_OUTPUT_COLOR`] at 1.
+fn output_color(value: f32) -> Color32 {
+    let t = value.clamp(0.0, 1.0);
+    let channel = |low: u8, high: u8| (low as f32 + (high as f32 - low as f32) * t).round() as u8;
+
+    Color32::from_rgb(
+        channel(LOW_OUTPUT_COLOR.r(), HIGH_OUTPUT_COLOR.r()),
+        channel(LOW_OUTPUT_COLOR.g(), HIGH_OUTPUT_COLOR.g()),
+        channel(LOW_OUTPUT_COLOR.b(), HIGH_OUTPUT_COLOR.b()),
+    )
+}
+
+/// Sweeps the first two inputs of a genome over a rectangle and colors each
+/// cell by one of its outputs, showing where it switches from one answer to
+/// the other.
+pub struct DecisionBoundary {
+    /// The bottom left corner of the swept rectangle.
+    pub min: [f32; 2],
+    /// The top right corner of the swept rectangle.
+    pub max: [f32; 2],
+    /// The output the cells are colored by.
+    pub output: usize,
+    /// How many cells there are along each side.
+    pub resolution: usize,
+    texture: Option<TextureHandle>,
+    /// Whether `texture` needs to be sampled again.
+    stale: bool,
+}
+
+impl Default for DecisionBoundary {
+    fn default() -> Self {
+        Self {
+            min: [0.0, 0.0],
+            max: [1.0, 1.0],
+            output: 0,
+            resolution: 32,
+            texture: None,
+            stale: true,
+        }
+    }
+}
+
+impl DecisionBoundary {
+    /// Samples again the next time it's shown, for when the genome or the
+    /// other inputs change.
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    /// The selected output at the center of each cell, row by row from the
+    /// top (largest second input) down, the way images are laid out. Inputs
+    /// past the first two are taken from `inputs`.
+    pub fn sample<const INPUT_SZ: usize, const OUTPUT_SZ: usize>(
+        &self,
+        genome: &Genome<INPUT_SZ, OUTPUT_SZ>,
+        mut inputs: [f32; INPUT_SZ],
+    ) -> Vec<f32> {
+        let cell_size = [
+            (self.max[0] - self.min[0]) / self.resolution as f32,
+            (self.max[1] - self.min[1]) / self.resolution as f32,
+        ];
+        let mut values = Vec::with_capacity(self.resolution * self.resolution);
+
+        for row in 0..self.resolution {
+            for column in 0..self.resolution {
+                inputs[0] = self.min[0] + (column as f32 + 0.5) * cell_size[0];
+                inputs[1] = self.max[1] - (row as f32 + 0.5) * cell_size[1];
+                values.push(genome.activate(inputs).output[self.output]);
+            }
+        }
+
+        values
+    }
+
+    pub fn show<const INPUT_SZ: usize, const OUTPUT_SZ: usize>(
+        &mut self,
+        ui: &mut egui::Ui,
+        id_source: impl std::hash::Hash,
+        genome: &Genome<INPUT_SZ, OUTPUT_SZ>,
+        inputs: [f32; INPUT_SZ],
+        test_cases: &[TestCase<INPUT_SZ, OUTPUT_SZ>],
+    ) {
+        let id = ui.make_persistent_id(id_source);
+
+        ui.horizontal(|ui| {
+            for (axis, name) in ["x", "y"].into_iter().enumerate() {
+                ui.label(name);
+                self.stale |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.min[axis])
+                            .clamp_range(f32::NEG_INFINITY..=self.max[axis] - 0.01)
+                            .speed(0.01),
+                    )
+                    .changed();
+                self.stale |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.max[axis])
+                            .clamp_range(self.min[axis] + 0.01..=f32::INFINITY)
+                            .speed(0.01),
+                    )
+                    .changed();
+            }
+
+            if OUTPUT_SZ > 1 {
+                egui::ComboBox::from_id_source(id.with("output"))
+                    .selected_text(format!("Output {}", self.output))
+                    .show_ui(ui, |ui| {
+                        for output in 0..OUTPUT_SZ {
+                            self.stale |= ui
+                                .selectable_value(
+                                    &mut self.output,
+                                    output,
+                                    format!("Output {}", output),
+                                )
+                                .changed();
+                        }
+                    });
+            }
+
+            self.stale |= ui
+                .add(
+                    egui::Slider::new(&mut self.resolution, MIN_RESOLUTION..=MAX_RESOLUTION)
+                        .text("Cells"),
+                )
+                .changed();
+        });
+
+        if self.stale || self.texture.is_none() {
+            let pixels = self
+                .sample(genome, inputs)
+                .into_iter()
+                .map(output_color)
+                .collect();
+            let image = ColorImage {
+                size: [self.resolution, self.resolution],
+                pixels,
+            };
+
+            match &mut self.texture {
+                Some(texture) => texture.set(image, TextureOptions::NEAREST),
+                None => {
+                    self.texture = Some(ui.ctx().load_texture(
+                        "decision_boundary",
+                        image,
+                        TextureOptions::NEAREST,
+                    ))
+                }
+            }
+            self.stale = false;
+        }
+
+        let texture = self.texture.as_ref().expect("Texture was just loaded");
+        let center = PlotPoint::new(
+            (self.min[0] + self.max[0]) / 2.0,
+            (self.min[1] + self.max[1]) / 2.0,
+        );
+        let size = Vec2::new(self.max[0] - self.min[0], self.max[1] - self.min[1]);
+        let outline = ui.visuals().text_color();
+
+        Plot::new(id.with("plot"))
+            .height(PLOT_HEIGHT)
+            .data_aspect(1.0)
+            .allow_scroll(false)
+            .show(ui, |plot_ui| {
+                plot_ui.image(PlotImage::new(texture, center, size));
+
+                for case in test_cases {
+                    let point = [case.input[0] as f64, case.input[1] as f64];
+
+                    plot_ui.points(
+                        Points::new(vec![point])
+                            .radius(POINT_RADIUS + POINT_OUTLINE)
+                            .color(outline),
+                    );
+                    plot_ui.points(
+                        Points::new(vec![point])
+                            .radius(POINT_RADIUS)
+                            .color(output_color(case.expected[self.output])),
+                    );
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{connection::Connection, node::Node};
+
+    #[test]
+    fn test_sample() {
+        // The output is sigmoid(x), ignoring y
+        let mut genome = Genome::<2, 1>::new();
+        genome.connections = vec![Connection {
+            in_node: Node(1),
+            out_node: Node(3),
+            weight: 1.0,
+            enabled: true,
+            innovation_number: 0,
+        }];
+
+        let boundary = DecisionBoundary {
+            min: [-4.0, 0.0],
+            max: [4.0, 2.0],
+            resolution: 4,
+            ..Default::default()
+        };
+        let values = boundary.sample(&genome, [0.0; 2]);
+
+        assert_eq!(values.len(), 16);
+        for row in values.chunks(4) {
+            assert_eq!(row, &values[..4]);
+            assert!(row.windows(2).all(|pair| pair[0] < pair[1]));
+            assert_eq!(row[1], genome.activate([-1.0, 0.0]).output[0]);
+        }
+    }
+}
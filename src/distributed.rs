@@ -0,0 +1,328 @@
+//! Spreads evaluation over worker processes connected over TCP.
+//!
+//! The protocol is line based. For every genome the coordinator sends
+//! `EVALUATE <client id> <genome>` where the genome uses the text format of
+//! [`Genome`]'s `Display` impl, and the worker answers with
+//! `RESULT <client id> <fitness>` followed by the optional parts of the
+//! [`EvaluationResult`]: any number of `metric <name>=<value>`, then
+//! `behavior <v1>,<v2>,...` and finally `error <message>`, which takes up the
+//! rest of the line. Metric names can't contain whitespace.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    client::ClientId,
+    environment::{Environment, PopulationEnvironment},
+    evaluation::{Evaluation, EvaluationResult},
+    genome::Genome,
+    population::Population,
+};
+
+struct Job {
+    /// Which call to `evaluate_population` the job is from.
+    batch: usize,
+    client_id: ClientId,
+    genome: String,
+}
+
+#[derive(Default)]
+struct JobQueue {
+    jobs: Mutex<VecDeque<Job>>,
+    available: Condvar,
+}
+
+impl JobQueue {
+    fn push(&self, job: Job) {
+        self.jobs.lock().unwrap().push_back(job);
+        self.available.notify_one();
+    }
+
+    fn pop(&self) -> Job {
+        let mut jobs = self.jobs.lock().unwrap();
+
+        loop {
+            if let Some(job) = jobs.pop_front() {
+                return job;
+            }
+
+            jobs = self.available.wait(jobs).unwrap();
+        }
+    }
+
+    fn clear(&self) {
+        self.jobs.lock().unwrap().clear();
+    }
+}
+
+/// The coordinator side. Workers connect to the address it is bound to, and
+/// every connected worker takes genomes off a shared queue. A worker that
+/// disconnects or doesn't answer within the timeout is dropped and its genome
+/// goes back on the queue for another worker. If no workers are left, the
+/// genomes that weren't evaluated get invalid results instead of waiting for
+/// one to connect, and results that arrive for them later are ignored.
+pub struct Distributed {
+    queue: Arc<JobQueue>,
+    results: Receiver<(usize, ClientId, EvaluationResult)>,
+    batch: usize,
+    local_addr: SocketAddr,
+    /// How many workers are connected, counting ones that died while idle
+    /// until they're handed a job.
+    workers: Arc<AtomicUsize>,
+    timeout: Duration,
+}
+
+impl Distributed {
+    pub fn bind(addr: impl ToSocketAddrs, timeout: Duration) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let queue = Arc::new(JobQueue::default());
+        let (sender, results) = channel();
+        let workers = Arc::new(AtomicUsize::new(0));
+
+        let accept_queue = queue.clone();
+        let accept_workers = workers.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let queue = accept_queue.clone();
+                let sender = sender.clone();
+                let workers = accept_workers.clone();
+
+                workers.fetch_add(1, Ordering::SeqCst);
+                thread::spawn(move || {
+                    serve_worker(stream, &queue, &sender, timeout);
+                    workers.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        Ok(Self {
+            queue,
+            results,
+            batch: 0,
+            local_addr,
+            workers,
+            timeout,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers.load(Ordering::SeqCst)
+    }
+}
+
+/// Feeds jobs to a single worker until it fails.
+fn serve_worker(
+    stream: TcpStream,
+    queue: &JobQueue,
+    results: &Sender<(usize, ClientId, EvaluationResult)>,
+    timeout: Duration,
+) {
+    if stream.set_read_timeout(Some(timeout)).is_err() || stream.set_nodelay(true).is_err() {
+        return;
+    }
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    let mut reader = BufReader::new(stream);
+
+    loop {
+        let job = queue.pop();
+
+        match request_result(&mut writer, &mut reader, &job) {
+            Some(result) => {
+                if results.send((job.batch, job.client_id, result)).is_err() {
+                    return;
+                }
+            }
+            None => {
+                queue.push(job);
+                return;
+            }
+        }
+    }
+}
+
+fn request_result(
+    writer: &mut TcpStream,
+    reader: &mut BufReader<TcpStream>,
+    job: &Job,
+) -> Option<EvaluationResult> {
+    let request = format!("EVALUATE {} {}\n", job.client_id, job.genome);
+    writer.write_all(request.as_bytes()).ok()?;
+
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+
+    let (client_id, result) = line
+        .trim_end_matches(['\r', '\n'])
+        .strip_prefix("RESULT ")?
+        .split_once(' ')?;
+    if client_id.parse::<ClientId>().ok()? != job.client_id {
+        return None;
+    }
+
+    parse_result(result)
+}
+
+fn format_result(result: &EvaluationResult) -> String {
+    let mut text = result.fitness.to_string();
+
+    for (name, value) in &result.metrics {
+        text += &format!(" metric {}={}", name, value);
+    }
+    if let Some(behavior) = &result.behavior {
+        let values = behavior.iter().map(f32::to_string).collect::<Vec<_>>();
+        text += &format!(" behavior {}", values.join(","));
+    }
+    if let Some(error) = &result.error {
+        // The message ends the line, so it can't span several
+        text += &format!(" error {}", error.replace('\n', " "));
+    }
+
+    text
+}
+
+fn parse_result(text: &str) -> Option<EvaluationResult> {
+    let (text, error) = match text.split_once(" error ") {
+        Some((text, error)) => (text, Some(error.to_string())),
+        None => (text, None),
+    };
+    let mut parts = text.split(' ');
+    let mut result = EvaluationResult::new(parts.next()?.parse().ok()?);
+    result.error = error;
+
+    while let Some(part) = parts.next() {
+        match part {
+            "metric" => {
+                let (name, value) = parts.next()?.split_once('=')?;
+                result.metrics.insert(name.to_string(), value.parse().ok()?);
+            }
+            "behavior" => {
+                result.behavior = Some(
+                    parts
+                        .next()?
+                        .split(',')
+                        .filter(|value| !value.is_empty())
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                        .ok()?,
+                );
+            }
+            _ => return None,
+        }
+    }
+
+    Some(result)
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> PopulationEnvironment<INPUT_SZ, OUTPUT_SZ>
+    for Distributed
+{
+    fn evaluate_population(
+        &mut self,
+        population: &mut Population<INPUT_SZ, OUTPUT_SZ>,
+    ) -> Evaluation {
+        self.batch += 1;
+        for (client_id, member) in population.members.iter().enumerate() {
+            self.queue.push(Job {
+                batch: self.batch,
+                client_id,
+                genome: member.to_string(),
+            });
+        }
+
+        let mut results = HashMap::new();
+        while results.len() < population.members.len() {
+            match self.results.recv_timeout(self.timeout) {
+                Ok((batch, client_id, result)) => {
+                    // Left over from an earlier call that gave up on it
+                    if batch == self.batch {
+                        results.insert(client_id, result);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) if self.workers() > 0 => {}
+                Err(_) => {
+                    self.queue.clear();
+                    for client_id in 0..population.members.len() {
+                        results.entry(client_id).or_insert_with(|| {
+                            EvaluationResult::invalid("No workers left to evaluate the genome")
+                        });
+                    }
+                }
+            }
+        }
+
+        Evaluation::new(results)
+    }
+}
+
+/// The worker side. Connects to the coordinator at `addr` and evaluates the
+/// genomes it sends with `env` until the connection is closed.
+pub fn run_worker<const INPUT_SZ: usize, const OUTPUT_SZ: usize>(
+    addr: impl ToSocketAddrs,
+    env: &mut impl Environment<INPUT_SZ, OUTPUT_SZ>,
+) -> io::Result<()> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, line.clone());
+
+        let (client_id, genome) = line
+            .strip_prefix("EVALUATE ")
+            .and_then(|rest| rest.split_once(' '))
+            .ok_or_else(invalid)?;
+        let mut genome = genome
+            .parse::<Genome<INPUT_SZ, OUTPUT_SZ>>()
+            .map_err(|_| invalid())?;
+
+        let result = env.evaluate(&mut genome);
+        let response = format!("RESULT {} {}\n", client_id, format_result(&result));
+        writer.write_all(response.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_round_trip() {
+        let results = [
+            EvaluationResult::new(0.5),
+            EvaluationResult::new(-1.25)
+                .with_metric("accuracy", 0.75)
+                .with_metric("steps", 12.0)
+                .with_behavior(vec![0.0, 1.5]),
+            EvaluationResult::new(2.0).with_behavior(vec![]),
+            EvaluationResult::invalid("Simulation error: position is NaN"),
+        ];
+
+        for result in results {
+            assert_eq!(parse_result(&format_result(&result)), Some(result));
+        }
+        assert_eq!(parse_result("1.0 unknown"), None);
+    }
+}
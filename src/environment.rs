@@ -1,5 +1,113 @@
-use crate::genome::Genome;
+use crate::{
+    evaluation::{Evaluation, EvaluationResult},
+    genome::Genome,
+    population::Population,
+};
+
+/// An input the environment checks and the output it wants for it.
+#[derive(Clone, Debug)]
+pub struct TestCase<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    pub name: String,
+    pub input: [f32; INPUT_SZ],
+    pub expected: [f32; OUTPUT_SZ],
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> TestCase<INPUT_SZ, OUTPUT_SZ> {
+    pub fn new(
+        name: impl Into<String>,
+        input: [f32; INPUT_SZ],
+        expected: [f32; OUTPUT_SZ],
+    ) -> Self {
+        Self {
+            name: name.into(),
+            input,
+            expected,
+        }
+    }
+
+    /// The squared difference from `expected`, summed over the outputs.
+    pub fn error(&self, actual: &[f32; OUTPUT_SZ]) -> f32 {
+        self.expected
+            .iter()
+            .zip(actual)
+            .map(|(expected, actual)| (actual - expected).powi(2))
+            .sum()
+    }
+}
+
+/// Splits a fitness between test cases, see
+/// [`TestCases::fitness_contributions`].
+pub type FitnessContributions = fn(&[f32]) -> Vec<f32>;
+
+/// The cases an environment is scored on, for showing how a genome does on
+/// each one.
+#[derive(Clone, Debug, Default)]
+pub struct TestCases<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    pub cases: Vec<TestCase<INPUT_SZ, OUTPUT_SZ>>,
+    /// How much of the fitness each case adds or takes away, given the
+    /// [`TestCase::error`] of every case in order. Only environments that
+    /// know how their fitness is made up from the errors can say.
+    pub fitness_contributions: Option<FitnessContributions>,
+}
 
 pub trait Environment<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
-    fn evaluate(&mut self, genome: &mut Genome<INPUT_SZ, OUTPUT_SZ>) -> f32;
+    fn evaluate(&mut self, genome: &mut Genome<INPUT_SZ, OUTPUT_SZ>) -> EvaluationResult;
+
+    /// The inputs and expected outputs the environment scores against, if it
+    /// has a fixed set, so they can be drawn over what a genome does.
+    fn training_points(&self) -> Vec<([f32; INPUT_SZ], [f32; OUTPUT_SZ])> {
+        Vec::new()
+    }
+
+    /// The [`Environment::training_points`] as cases to check one by one,
+    /// named after their inputs unless overridden.
+    fn test_cases(&self) -> TestCases<INPUT_SZ, OUTPUT_SZ> {
+        TestCases {
+            cases: self
+                .training_points()
+                .into_iter()
+                .map(|(input, expected)| TestCase::new(format!("{:?}", input), input, expected))
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Scores a whole population at once. Every [`Environment`] does this by
+/// evaluating each member on its own, but some modes, such as coevolution,
+/// need to see the other members.
+pub trait PopulationEnvironment<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    fn evaluate_population(
+        &mut self,
+        population: &mut Population<INPUT_SZ, OUTPUT_SZ>,
+    ) -> Evaluation;
+
+    /// See [`Environment::training_points`].
+    fn training_points(&self) -> Vec<([f32; INPUT_SZ], [f32; OUTPUT_SZ])> {
+        Vec::new()
+    }
+
+    /// See [`Environment::test_cases`].
+    fn test_cases(&self) -> TestCases<INPUT_SZ, OUTPUT_SZ> {
+        TestCases::default()
+    }
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize, E: Environment<INPUT_SZ, OUTPUT_SZ>>
+    PopulationEnvironment<INPUT_SZ, OUTPUT_SZ> for E
+{
+    fn evaluate_population(
+        &mut self,
+        population: &mut Population<INPUT_SZ, OUTPUT_SZ>,
+    ) -> Evaluation {
+        population.evaluate(self)
+    }
+
+    fn training_points(&self) -> Vec<([f32; INPUT_SZ], [f32; OUTPUT_SZ])> {
+        Environment::training_points(self)
+    }
+
+    fn test_cases(&self) -> TestCases<INPUT_SZ, OUTPUT_SZ> {
+        Environment::test_cases(self)
+    }
 }
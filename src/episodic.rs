@@ -0,0 +1,171 @@
+use crate::{
+    environment::Environment,
+    evaluation::EvaluationResult,
+    genome::{Genome, GenomeActivation},
+};
+
+/// A control task that is played one step at a time, in the style of a Gym
+/// environment.
+pub trait EpisodicEnvironment<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    /// Starts a new episode and returns the first observation.
+    fn reset(&mut self) -> [f32; INPUT_SZ];
+
+    /// Applies `action` and returns the next observation, the reward for this
+    /// step and whether the episode is over.
+    fn step(&mut self, action: [f32; OUTPUT_SZ]) -> ([f32; INPUT_SZ], f32, bool);
+}
+
+/// Adapts an [`EpisodicEnvironment`] into an [`Environment`] by running the
+/// genome's network through `episodes` episodes and summing the rewards.
+///
+/// The network is stateful: every step feeds the observation into the
+/// activation left over from the previous step, so recurrent connections
+/// carry information between steps. The state is cleared between episodes.
+pub struct Episodic<E> {
+    pub env: E,
+    pub episodes: usize,
+    pub max_episode_length: usize,
+}
+
+impl<E> Episodic<E> {
+    pub fn new(env: E, episodes: usize, max_episode_length: usize) -> Self {
+        Self {
+            env,
+            episodes,
+            max_episode_length,
+        }
+    }
+}
+
+impl<
+        const INPUT_SZ: usize,
+        const OUTPUT_SZ: usize,
+        E: EpisodicEnvironment<INPUT_SZ, OUTPUT_SZ>,
+    > Environment<INPUT_SZ, OUTPUT_SZ> for Episodic<E>
+{
+    fn evaluate(&mut self, genome: &mut Genome<INPUT_SZ, OUTPUT_SZ>) -> EvaluationResult {
+        let mut total_reward = 0.0;
+        let mut steps = 0;
+
+        for _ in 0..self.episodes {
+            let mut observation = self.env.reset();
+            let mut state = GenomeActivation::new(observation, genome.hidden_nodes);
+
+            for _ in 0..self.max_episode_length {
+                // `activate_step` reads the inputs from the last activation
+                state.input = observation;
+                let mut next_state = GenomeActivation::new(observation, genome.hidden_nodes);
+                genome.activate_step::<[f32; INPUT_SZ]>(&mut next_state, &state);
+
+                let (next_observation, reward, done) = self.env.step(next_state.output);
+                total_reward += reward;
+                steps += 1;
+
+                observation = next_observation;
+                state = next_state;
+
+                if done {
+                    break;
+                }
+            }
+        }
+
+        EvaluationResult::new(total_reward)
+            .with_metric("steps", steps as f32 / self.episodes.max(1) as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{connection::Connection, node::Node};
+
+    /// Rewards every step with 1 and ends the episode after `length` steps.
+    struct Countdown {
+        length: usize,
+        remaining: usize,
+        resets: usize,
+    }
+
+    impl EpisodicEnvironment<1, 1> for Countdown {
+        fn reset(&mut self) -> [f32; 1] {
+            self.remaining = self.length;
+            self.resets += 1;
+            [0.0]
+        }
+
+        fn step(&mut self, _action: [f32; 1]) -> ([f32; 1], f32, bool) {
+            self.remaining -= 1;
+            ([0.0], 1.0, self.remaining == 0)
+        }
+    }
+
+    /// Flips the observation between 1 and -1 every step and rewards
+    /// actions that match the observation they were given.
+    struct Flip {
+        observation: f32,
+    }
+
+    impl EpisodicEnvironment<1, 1> for Flip {
+        fn reset(&mut self) -> [f32; 1] {
+            self.observation = 1.0;
+            [self.observation]
+        }
+
+        fn step(&mut self, action: [f32; 1]) -> ([f32; 1], f32, bool) {
+            let reward = if (action[0] > 0.5) == (self.observation > 0.0) {
+                1.0
+            } else {
+                0.0
+            };
+            self.observation = -self.observation;
+            ([self.observation], reward, false)
+        }
+    }
+
+    #[test]
+    fn test_acts_on_current_observation() {
+        // The output follows the input
+        let mut genome = Genome::<1, 1>::new();
+        genome.connections = vec![Connection {
+            in_node: Node(1),
+            out_node: Node(2),
+            weight: 10.0,
+            enabled: true,
+            innovation_number: 0,
+        }];
+
+        let mut flip = Episodic::new(Flip { observation: 1.0 }, 2, 6);
+        assert_eq!(flip.evaluate(&mut genome).fitness, 12.0);
+    }
+
+    #[test]
+    fn test_episodes_are_summed_and_truncated() {
+        let mut genome = Genome::<1, 1>::new();
+
+        let mut short = Episodic::new(
+            Countdown {
+                length: 3,
+                remaining: 0,
+                resets: 0,
+            },
+            4,
+            10,
+        );
+        let result = short.evaluate(&mut genome);
+        assert_eq!(result.fitness, 12.0);
+        assert_eq!(result.metrics["steps"], 3.0);
+        assert_eq!(short.env.resets, 4);
+
+        let mut truncated = Episodic::new(
+            Countdown {
+                length: 30,
+                remaining: 0,
+                resets: 0,
+            },
+            2,
+            5,
+        );
+        assert_eq!(truncated.evaluate(&mut genome).fitness, 10.0);
+    }
+}
@@ -1,14 +1,126 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use crate::{client::ClientId, species::Species};
 
+/// What an environment reports about a single genome.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvaluationResult {
+    pub fitness: f32,
+    /// Named auxiliary metrics, e.g. accuracy or steps survived.
+    pub metrics: BTreeMap<String, f32>,
+    /// Describes how the genome behaved, for novelty search and the like.
+    pub behavior: Option<Vec<f32>>,
+    /// Set when the genome couldn't be evaluated properly.
+    pub error: Option<String>,
+}
+
+impl EvaluationResult {
+    pub fn new(fitness: f32) -> Self {
+        Self {
+            fitness,
+            ..Default::default()
+        }
+    }
+
+    /// A genome that couldn't be evaluated, e.g. because the simulation
+    /// blew up. It is treated as the least fit member of the population.
+    pub fn invalid(error: impl Into<String>) -> Self {
+        Self {
+            error: Some(error.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_metric(mut self, name: impl Into<String>, value: f32) -> Self {
+        self.metrics.insert(name.into(), value);
+        self
+    }
+
+    pub fn with_behavior(mut self, behavior: Vec<f32>) -> Self {
+        self.behavior = Some(behavior);
+        self
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl From<f32> for EvaluationResult {
+    fn from(fitness: f32) -> Self {
+        Self::new(fitness)
+    }
+}
+
 #[derive(Clone)]
 pub struct Evaluation {
     pub fitness: HashMap<ClientId, f32>,
-    pub adjusted: bool,
+    pub results: HashMap<ClientId, EvaluationResult>,
 }
 
 impl Evaluation {
+    /// Invalid results get the lowest fitness of the valid ones so they are
+    /// the first to be culled.
+    pub fn new(results: HashMap<ClientId, EvaluationResult>) -> Self {
+        let lowest_valid_fitness = results
+            .values()
+            .filter(|result| result.is_valid())
+            .map(|result| result.fitness)
+            .min_by(|a, b| a.total_cmp(b))
+            .unwrap_or(0.0);
+
+        Self {
+            fitness: results
+                .iter()
+                .map(|(id, result)| {
+                    let fitness = if result.is_valid() {
+                        result.fitness
+                    } else {
+                        lowest_valid_fitness
+                    };
+
+                    (*id, fitness)
+                })
+                .collect(),
+            results,
+        }
+    }
+
+    /// Sets the result of a single member, e.g. to replace it with an
+    /// immigrant from another island.
+    pub fn insert(&mut self, id: ClientId, result: EvaluationResult) {
+        self.fitness.insert(id, result.fitness);
+        self.results.insert(id, result);
+    }
+
+    pub fn metric_names(&self) -> BTreeSet<&str> {
+        self.results
+            .values()
+            .flat_map(|result| result.metrics.keys().map(String::as_str))
+            .collect()
+    }
+
+    /// Averaged over the members that report the metric.
+    pub fn average_metric(&self, name: &str) -> Option<f32> {
+        let values = self
+            .results
+            .values()
+            .filter_map(|result| result.metrics.get(name))
+            .collect::<Vec<_>>();
+
+        if values.is_empty() {
+            None
+        } else {
+            Some(values.iter().copied().sum::<f32>() / values.len() as f32)
+        }
+    }
+
+    pub fn invalid_count(&self) -> usize {
+        self.results
+            .values()
+            .filter(|result| !result.is_valid())
+            .count()
+    }
     pub fn species_average_fitness<const INPUT_SZ: usize, const OUTPUT_SZ: usize>(
         &self,
         species: &Species<INPUT_SZ, OUTPUT_SZ>,
@@ -97,3 +209,30 @@ impl Evaluation {
             .expect("Can't find champion of empty species")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results() {
+        let evaluation = Evaluation::new(
+            [
+                (0, EvaluationResult::new(2.0).with_metric("accuracy", 0.5)),
+                (1, EvaluationResult::new(-1.0).with_metric("accuracy", 1.0)),
+                (2, EvaluationResult::invalid("Diverged")),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        assert_eq!(evaluation.fitness[&2], -1.0);
+        assert_eq!(evaluation.invalid_count(), 1);
+        assert_eq!(
+            evaluation.metric_names().into_iter().collect::<Vec<_>>(),
+            vec!["accuracy"]
+        );
+        assert_eq!(evaluation.average_metric("accuracy"), Some(0.75));
+        assert_eq!(evaluation.average_metric("steps"), None);
+    }
+}
@@ -1,7 +1,15 @@
 use crate::{
-    client::ClientId, environment::Environment, evaluation::Evaluation, evaluator::Evaluator,
-    genome::GenomeActivation, population::Population, population_manager::PopulationManager,
-    speciation::Speciation,
+    client::ClientId,
+    config::NeatConfig,
+    config_editor::{ConfigEditor, ParameterChange},
+    environment::PopulationEnvironment,
+    evaluator::Evaluator,
+    evolution_thread::{EvolutionThread, Generation, RunMode},
+    export::{line_plot, ExportMenu, Series},
+    genome::GenomeActivation,
+    lineage::Lineage,
+    population_manager::PopulationManager,
+    species_chart::SpeciesChart,
 };
 
 use eframe::{
@@ -9,63 +17,192 @@ use eframe::{
         self,
         plot::{Corner, Legend},
     },
-    epaint::Vec2,
+    epaint::{Color32, Vec2},
 };
 
-struct Generation<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
-    population: Population<INPUT_SZ, OUTPUT_SZ>,
-    speciation: Speciation<INPUT_SZ, OUTPUT_SZ>,
-    evaluation: Evaluation,
-}
+const CHAMPION_FITNESS_COLOR: Color32 = Color32::from_rgb(90, 170, 255);
+const AVERAGE_FITNESS_COLOR: Color32 = Color32::from_rgb(255, 170, 60);
 
 pub struct EvaluationManager<
     const INPUT_SZ: usize,
     const OUTPUT_SZ: usize,
-    E: Environment<INPUT_SZ, OUTPUT_SZ>,
+    E: PopulationEnvironment<INPUT_SZ, OUTPUT_SZ>,
 > {
-    evaluator: Evaluator<INPUT_SZ, OUTPUT_SZ, E>,
+    /// Handed over to `thread` once the run starts.
+    evaluator: Option<Evaluator<INPUT_SZ, OUTPUT_SZ, E>>,
+    thread: Option<EvolutionThread<INPUT_SZ, OUTPUT_SZ, E>>,
+    /// Kept up to date with the evaluator's lineage through the snapshots.
+    lineage: Lineage<INPUT_SZ, OUTPUT_SZ>,
     population_manager: PopulationManager<INPUT_SZ, OUTPUT_SZ>,
-    history: Vec<Generation<INPUT_SZ, OUTPUT_SZ>>,
+    /// Indexed by generation, then by island.
+    history: Vec<Vec<Generation<INPUT_SZ, OUTPUT_SZ>>>,
     selected_generation: usize,
+    selected_island: usize,
+    /// How many generations "Step" runs.
+    step_count: usize,
+    target_fitness: f32,
+    /// Caps how many generations the evolution thread runs before the next
+    /// frame is drawn.
+    generations_per_frame: usize,
+    /// Whether `selected_generation` moves to every new generation.
+    follow_latest: bool,
+    /// The parameters the evaluator is running with, or will be once the
+    /// thread gets to the updates.
+    config: NeatConfig,
+    target_size: usize,
+    config_open: bool,
+    updates_sent: usize,
+    /// Changes the thread hasn't evolved a generation with yet, along with
+    /// how many updates had to be applied for them to take effect.
+    pending_changes: Vec<(usize, &'static str, String)>,
+    parameter_changes: Vec<ParameterChange>,
+    fitness_export: ExportMenu,
 }
 
-impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize, E: Environment<INPUT_SZ, OUTPUT_SZ>>
-    EvaluationManager<INPUT_SZ, OUTPUT_SZ, E>
+impl<
+        const INPUT_SZ: usize,
+        const OUTPUT_SZ: usize,
+        E: PopulationEnvironment<INPUT_SZ, OUTPUT_SZ> + Send + 'static,
+    > EvaluationManager<INPUT_SZ, OUTPUT_SZ, E>
 {
     pub fn new(evaluator: Evaluator<INPUT_SZ, OUTPUT_SZ, E>) -> Self {
+        let population_manager = PopulationManager::new(evaluator.env.test_cases());
+
         Self {
-            evaluator,
-            population_manager: PopulationManager::default(),
+            config: evaluator.config(),
+            target_size: evaluator.target_size(),
+            config_open: false,
+            updates_sent: 0,
+            pending_changes: Vec::new(),
+            parameter_changes: Vec::new(),
+            fitness_export: ExportMenu::new("fitness.svg"),
+            evaluator: Some(evaluator),
+            thread: None,
+            lineage: Lineage::default(),
+            population_manager,
             history: Vec::new(),
             selected_generation: 0,
+            selected_island: 0,
+            step_count: 10,
+            target_fitness: 0.95,
+            generations_per_frame: 1,
+            follow_latest: true,
         }
     }
 
-    pub fn evaluate_and_evolve(&mut self) {
-        self.evaluator.evaluate_and_evolve();
-        self.history.push(Generation {
-            population: self.evaluator.population.clone(),
-            speciation: self.evaluator.last_speciation.clone().unwrap(),
-            evaluation: self.evaluator.last_evaluation.clone().unwrap(),
-        });
+    /// Takes every finished generation from the evolution thread and lets it
+    /// run up to `generations_per_frame` more.
+    fn receive(&mut self) {
+        let Some(thread) = &self.thread else {
+            return;
+        };
+
+        thread.allow(self.generations_per_frame);
+
+        while let Some(snapshot) = thread.try_recv() {
+            self.lineage.merge(snapshot.lineage);
+            self.history.push(snapshot.islands);
+
+            let generation = self.history.len();
+            for (_, name, value) in self
+                .pending_changes
+                .extract_if(.., |(update, _, _)| *update <= snapshot.updates_applied)
+            {
+                // Dragging a slider changes a parameter many times over
+                if let Some(change) = self
+                    .parameter_changes
+                    .iter_mut()
+                    .find(|change| change.generation == generation && change.name == name)
+                {
+                    change.value = value;
+                } else {
+                    self.parameter_changes.push(ParameterChange {
+                        generation,
+                        name,
+                        value,
+                    });
+                }
+            }
+
+            if self.follow_latest {
+                self.selected_generation = self.history.len() - 1;
+            }
+        }
+    }
+
+    fn show_config(&mut self, ctx: &egui::Context) {
+        let Some(thread) = &self.thread else {
+            return;
+        };
+
+        egui::Window::new("Parameters")
+            .open(&mut self.config_open)
+            .show(ctx, |ui| {
+                let changes = ConfigEditor {
+                    config: &mut self.config,
+                    target_size: &mut self.target_size,
+                }
+                .show(ui);
+
+                if !changes.is_empty() {
+                    let config = self.config;
+                    let target_size = self.target_size;
+
+                    thread.update(move |evaluator| {
+                        evaluator.set_config(config);
+                        evaluator.set_target_size(target_size);
+                    });
+                    self.updates_sent += 1;
+                    self.pending_changes.extend(
+                        changes
+                            .into_iter()
+                            .map(|(name, value)| (self.updates_sent, name, value)),
+                    );
+                }
+
+                if !self.parameter_changes.is_empty() {
+                    ui.separator();
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for change in &self.parameter_changes {
+                            ui.label(format!(
+                                "Generation {}: {} = {}",
+                                change.generation, change.name, change.value
+                            ));
+                        }
+                    });
+                }
+            });
     }
 
     pub fn show(&mut self, ctx: &egui::Context) {
-        if self.history.is_empty() {
+        self.receive();
+
+        let Some(thread) = &self.thread else {
             egui::CentralPanel::default().show(ctx, |ui| {
                 ui.centered_and_justified(|ui| {
                     if ui.button("Start").clicked() {
-                        self.evaluate_and_evolve()
+                        let evaluator = self.evaluator.take().expect("Run was already started");
+                        let thread = EvolutionThread::spawn(evaluator, ctx.clone());
+                        thread.set_run_mode(RunMode::Steps(1));
+                        self.thread = Some(thread);
                     }
                 });
             });
             return;
+        };
+
+        if self.history.is_empty() {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.centered_and_justified(|ui| ui.spinner());
+            });
+            return;
         }
 
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             ui.horizontal_centered(|ui| {
                 if ui.button("<").clicked() {
                     self.selected_generation = self.selected_generation.saturating_sub(1);
+                    self.follow_latest = false;
                 }
                 ui.label(format!(
                     "{}/{}",
@@ -73,36 +210,123 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize, E: Environment<INPUT_SZ, OUT
                     self.history.len()
                 ));
                 if ui.button(">").clicked() {
-                    self.selected_generation += 1;
-                    while self.selected_generation >= self.history.len() {
-                        self.evaluate_and_evolve();
+                    if self.selected_generation + 1 < self.history.len() {
+                        self.selected_generation += 1;
+                    } else if thread.run_mode() == RunMode::Paused {
+                        self.follow_latest = true;
+                        thread.set_run_mode(RunMode::Steps(1));
                     }
                 }
+                ui.checkbox(&mut self.follow_latest, "Follow latest");
+
+                ui.separator();
+
+                if thread.is_cancelled() {
+                    ui.label("Cancelled");
+                } else {
+                    if thread.run_mode() == RunMode::Paused {
+                        if ui.button("Play").clicked() {
+                            thread.set_run_mode(RunMode::Playing);
+                        }
+                    } else {
+                        if ui.button("Pause").clicked() {
+                            thread.set_run_mode(RunMode::Paused);
+                        }
+                        ui.spinner();
+                    }
+
+                    ui.add(egui::DragValue::new(&mut self.step_count).clamp_range(1..=10_000));
+                    if ui.button("Step").clicked() {
+                        thread.set_run_mode(RunMode::Steps(self.step_count));
+                    }
+
+                    ui.add(egui::DragValue::new(&mut self.target_fitness).speed(0.01));
+                    if ui.button("Run until fitness").clicked() {
+                        thread.set_run_mode(RunMode::UntilFitness(self.target_fitness));
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        thread.cancel();
+                    }
+                }
+
+                ui.separator();
+                ui.toggle_value(&mut self.config_open, "Parameters");
+                ui.label("Generations per frame");
+                ui.add(egui::DragValue::new(&mut self.generations_per_frame).clamp_range(1..=100));
+
+                let islands = self.history.last().map_or(0, Vec::len);
+                if islands > 1 {
+                    ui.separator();
+                    egui::ComboBox::from_id_source("island")
+                        .selected_text(format!("Island {}", self.selected_island + 1))
+                        .show_ui(ui, |ui| {
+                            for island in 0..islands {
+                                ui.selectable_value(
+                                    &mut self.selected_island,
+                                    island,
+                                    format!("Island {}", island + 1),
+                                );
+                            }
+                        });
+                }
             });
         });
 
+        self.show_config(ctx);
+
         self.selected_generation = self
             .selected_generation
             .clamp(0, self.history.len().saturating_sub(1));
 
-        egui::Window::new("My Window").show(ctx, |ui| {
-            use egui::plot::{Line, Plot, PlotPoints};
-            let max_fitness: PlotPoints = self
-                .history
-                .iter()
-                .enumerate()
-                .map(|(i, gen)| [i as f64, gen.evaluation.champion().1 as f64])
-                .collect();
-            let max_fitness_line = Line::new(max_fitness).name("Champion Fitness");
-            let avg_fitness: PlotPoints = self
-                .history
+        let clicked_species = egui::Window::new("My Window").show(ctx, |ui| {
+            use egui::plot::{Line, Plot, VLine};
+            let series = [
+                Series {
+                    name: "Champion Fitness".to_owned(),
+                    color: CHAMPION_FITNESS_COLOR,
+                    points: self
+                        .history
+                        .iter()
+                        .enumerate()
+                        .map(|(i, gen)| {
+                            [
+                                i as f64,
+                                gen[self.selected_island].evaluation.champion().1 as f64,
+                            ]
+                        })
+                        .collect(),
+                },
+                Series {
+                    name: "Average Fitness".to_owned(),
+                    color: AVERAGE_FITNESS_COLOR,
+                    points: self
+                        .history
+                        .iter()
+                        .enumerate()
+                        .map(|(i, gen)| {
+                            [
+                                i as f64,
+                                gen[self.selected_island].evaluation.average_fitness() as f64,
+                            ]
+                        })
+                        .collect(),
+                },
+            ];
+            let markers = self
+                .parameter_changes
                 .iter()
-                .enumerate()
-                .map(|(i, gen)| [i as f64, gen.evaluation.average_fitness() as f64])
-                .collect();
-            let avg_fitness_line = Line::new(avg_fitness).name("Average Fitness");
+                .map(|change| (change.generation - 1) as f64)
+                .collect::<Vec<_>>();
+
+            let export = ui
+                .horizontal(|ui| {
+                    ui.label("Fitness");
+                    self.fitness_export.show(ui)
+                })
+                .inner;
 
-            Plot::new("my_plot")
+            let plot = Plot::new("my_plot")
                 .clamp_grid(true)
                 .allow_drag(false)
                 .allow_zoom(false)
@@ -118,19 +342,65 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize, E: Environment<INPUT_SZ, OUT
                 .legend(Legend::default().position(Corner::RightBottom))
                 .view_aspect(2.0)
                 .show(ui, |plot_ui| {
-                    plot_ui.line(max_fitness_line);
-                    plot_ui.line(avg_fitness_line);
+                    for series in &series {
+                        plot_ui.line(
+                            Line::new(series.points.clone())
+                                .name(&series.name)
+                                .color(series.color),
+                        );
+                    }
+                    for &marker in &markers {
+                        plot_ui.vline(
+                            VLine::new(marker)
+                                .name("Parameter change")
+                                .color(Color32::GRAY),
+                        );
+                    }
                 });
+
+            if export {
+                self.fitness_export.save(&line_plot(
+                    plot.response.rect.size(),
+                    ui.style(),
+                    &series,
+                    &markers,
+                ));
+            }
+
+            ui.label("Species");
+            SpeciesChart::new(
+                self.history
+                    .iter()
+                    .map(|gen| &gen[self.selected_island].speciation),
+            )
+            .markers(markers)
+            .show(ui)
         });
 
+        if let Some((generation, species)) = clicked_species.and_then(|response| response.inner?) {
+            self.selected_generation = generation;
+            self.follow_latest = false;
+            let Generation {
+                population,
+                speciation,
+                evaluation,
+            } = &self.history[generation][self.selected_island];
+
+            self.population_manager
+                .open_species_champion(population, speciation, evaluation, species);
+        }
+
         if let Some(Generation {
             population,
             speciation,
             evaluation,
-        }) = self.history.get_mut(self.selected_generation)
+        }) = self
+            .history
+            .get_mut(self.selected_generation)
+            .and_then(|islands| islands.get_mut(self.selected_island))
         {
             self.population_manager
-                .show(ctx, population, speciation, evaluation);
+                .show(ctx, population, speciation, evaluation, &self.lineage);
         }
     }
 }
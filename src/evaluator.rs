@@ -1,60 +1,192 @@
-use std::mem::swap;
+use std::{collections::HashSet, mem::swap};
 
-use rand::{rngs::ThreadRng, thread_rng};
+use rand::{rngs::StdRng, SeedableRng};
 
 use crate::{
-    environment::Environment, evaluation::Evaluation, innovation_record::InnovationRecord,
-    population::Population, speciation::Speciation,
+    config::NeatConfig,
+    environment::PopulationEnvironment,
+    innovation_record::InnovationRecord,
+    island::{Island, Migration},
+    lineage::{GenomeId, Lineage},
+    reporter::Reporter,
 };
 
+/// How many generations back the lineage keeps every genome, so the ancestry
+/// of the living ones can be walked that far.
+const GENOME_HISTORY: usize = 50;
+
 pub struct Evaluator<
     const INPUT_SZ: usize,
     const OUTPUT_SZ: usize,
-    E: Environment<INPUT_SZ, OUTPUT_SZ>,
+    E: PopulationEnvironment<INPUT_SZ, OUTPUT_SZ>,
 > {
     pub env: E,
     pub innovation_record: InnovationRecord<INPUT_SZ, OUTPUT_SZ>,
-    pub population: Population<INPUT_SZ, OUTPUT_SZ>,
-    pub rng: ThreadRng,
-    pub last_speciation: Option<Speciation<INPUT_SZ, OUTPUT_SZ>>,
-    pub last_evaluation: Option<Evaluation>,
+    pub islands: Vec<Island<INPUT_SZ, OUTPUT_SZ>>,
+    pub lineage: Lineage<INPUT_SZ, OUTPUT_SZ>,
+    pub migration: Option<Migration>,
+    pub reporters: Vec<Box<dyn Reporter<INPUT_SZ, OUTPUT_SZ> + Send>>,
+    pub champion_fitness: Option<f32>,
+    /// The genome with `champion_fitness`, which the lineage keeps along
+    /// with the living and recent ones.
+    pub champion: Option<GenomeId>,
+    /// Unlike `ThreadRng` this can be sent along with the evaluator to the
+    /// thread running the evolution.
+    pub rng: StdRng,
 }
 
-impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize, E: Environment<INPUT_SZ, OUTPUT_SZ>>
-    Evaluator<INPUT_SZ, OUTPUT_SZ, E>
+impl<
+        const INPUT_SZ: usize,
+        const OUTPUT_SZ: usize,
+        E: PopulationEnvironment<INPUT_SZ, OUTPUT_SZ>,
+    > Evaluator<INPUT_SZ, OUTPUT_SZ, E>
 {
     pub fn new(env: E, target_size: usize) -> Self {
-        let mut rng = thread_rng();
+        Self::with_islands(env, target_size, 1, None)
+    }
+
+    /// Evolves `islands` independent populations of `target_size` that share
+    /// one innovation record, with champions moving between them according
+    /// to `migration`.
+    pub fn with_islands(
+        env: E,
+        target_size: usize,
+        islands: usize,
+        migration: Option<Migration>,
+    ) -> Self {
+        let mut rng = StdRng::from_entropy();
         let mut innovation_record = InnovationRecord::default();
+        let mut lineage = Lineage::default();
 
         Self {
-            population: Population::new(&mut rng, &mut innovation_record, target_size),
+            islands: (0..islands)
+                .map(|_| Island::new(&mut rng, &mut innovation_record, &mut lineage, target_size))
+                .collect(),
+            lineage,
             env,
             innovation_record,
+            migration,
+            reporters: Vec::new(),
+            champion_fitness: None,
+            champion: None,
             rng,
-            last_speciation: None,
-            last_evaluation: None,
         }
     }
 
+    pub fn with_reporters(
+        mut self,
+        reporters: Vec<Box<dyn Reporter<INPUT_SZ, OUTPUT_SZ> + Send>>,
+    ) -> Self {
+        self.reporters = reporters;
+        self
+    }
+
+    pub fn config(&self) -> NeatConfig {
+        self.islands
+            .first()
+            .map(|island| island.population.config)
+            .unwrap_or_default()
+    }
+
+    /// Takes effect from the next generation on every island.
+    pub fn set_config(&mut self, config: NeatConfig) {
+        for island in &mut self.islands {
+            island.population.config = config;
+        }
+    }
+
+    pub fn target_size(&self) -> usize {
+        self.islands
+            .first()
+            .map(|island| island.population.target_size)
+            .unwrap_or(0)
+    }
+
+    pub fn set_target_size(&mut self, target_size: usize) {
+        for island in &mut self.islands {
+            island.population.target_size = target_size;
+        }
+    }
+
+    pub fn generation(&self) -> usize {
+        self.islands
+            .first()
+            .map(|island| island.population.generation)
+            .unwrap_or(0)
+    }
+
     pub fn evaluate_and_evolve(&mut self) {
-        if let (Some(speciation), Some(evaluation)) =
-            (&mut self.last_speciation, &mut self.last_evaluation)
-        {
-            self.population.evolve(
-                evaluation,
-                speciation,
+        let generation = self.generation() + 1;
+        for reporter in &mut self.reporters {
+            reporter.on_generation_start(generation);
+        }
+
+        for (island_index, island) in self.islands.iter_mut().enumerate() {
+            let extinct = island.evaluate_and_evolve(
+                &mut self.env,
                 &mut self.rng,
                 &mut self.innovation_record,
+                &mut self.lineage,
             );
+
+            let speciation = island.last_speciation.as_ref().unwrap();
+            let evaluation = island.last_evaluation.as_ref().unwrap();
+            let (champion, champion_fitness) = evaluation.champion();
+            let new_champion = self
+                .champion_fitness
+                .is_none_or(|best| champion_fitness > best);
+
+            for reporter in &mut self.reporters {
+                reporter.on_speciated(island_index, speciation);
+                for &species in &extinct {
+                    reporter.on_species_extinct(island_index, species);
+                }
+                reporter.on_evaluated(island_index, evaluation);
+                if new_champion {
+                    reporter.on_new_champion(
+                        island_index,
+                        &island.population.members[champion],
+                        champion_fitness,
+                    );
+                }
+            }
+
+            if new_champion {
+                self.champion_fitness = Some(champion_fitness);
+                self.champion = Some(island.population.members[champion].id);
+            }
+        }
+
+        if let Some(migration) = self.migration {
+            if migration.interval > 0 && self.generation().is_multiple_of(migration.interval) {
+                self.migrate(migration);
+            }
         }
 
-        self.last_speciation = Some(self.population.speciate(
-            &mut self.rng,
-            self.last_speciation.as_ref(),
-            self.last_evaluation.as_ref(),
-        ));
+        let keep = self
+            .islands
+            .iter()
+            .flat_map(|island| &island.population.members)
+            .map(|member| member.id)
+            .chain(self.champion)
+            .collect::<HashSet<_>>();
+        self.lineage
+            .forget_genomes(self.generation().saturating_sub(GENOME_HISTORY), &keep);
+    }
+
+    fn migrate(&mut self, migration: Migration) {
+        let champions = self
+            .islands
+            .iter()
+            .map(|island| island.champion())
+            .collect::<Vec<_>>();
 
-        self.last_evaluation = Some(self.population.evaluate(&mut self.env));
+        for (from, champion) in champions.into_iter().enumerate() {
+            if let Some((genome, result)) = champion {
+                for to in migration.topology.neighbours(from, self.islands.len()) {
+                    self.islands[to].immigrate(genome.clone(), result.clone(), &mut self.lineage);
+                }
+            }
+        }
     }
 }
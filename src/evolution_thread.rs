@@ -0,0 +1,287 @@
+use std::{
+    mem::take,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use eframe::egui;
+
+use crate::{
+    environment::PopulationEnvironment, evaluation::Evaluation, evaluator::Evaluator,
+    lineage::Lineage, population::Population, speciation::Speciation,
+};
+
+/// How the evolution thread advances generations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunMode {
+    Paused,
+    Playing,
+    /// Runs this many more generations.
+    Steps(usize),
+    /// Runs until a champion reaches this fitness.
+    UntilFitness(f32),
+}
+
+/// One island after a generation has been evaluated.
+pub struct Generation<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    pub population: Population<INPUT_SZ, OUTPUT_SZ>,
+    pub speciation: Speciation<INPUT_SZ, OUTPUT_SZ>,
+    pub evaluation: Evaluation,
+}
+
+pub struct Snapshot<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    /// Indexed by island.
+    pub islands: Vec<Generation<INPUT_SZ, OUTPUT_SZ>>,
+    /// What changed in the lineage during the generation, see
+    /// [`Lineage::take_changes`].
+    pub lineage: Lineage<INPUT_SZ, OUTPUT_SZ>,
+    /// How many of the updates passed to [`EvolutionThread::update`] were
+    /// applied before this generation.
+    pub updates_applied: usize,
+}
+
+type Update<const INPUT_SZ: usize, const OUTPUT_SZ: usize, E> =
+    Box<dyn FnOnce(&mut Evaluator<INPUT_SZ, OUTPUT_SZ, E>) + Send>;
+
+struct Control<
+    const INPUT_SZ: usize,
+    const OUTPUT_SZ: usize,
+    E: PopulationEnvironment<INPUT_SZ, OUTPUT_SZ>,
+> {
+    run_mode: RunMode,
+    updates: Vec<Update<INPUT_SZ, OUTPUT_SZ, E>>,
+    /// How many more generations can run before the UI allows more, see
+    /// [`EvolutionThread::allow`].
+    budget: usize,
+    cancelled: bool,
+}
+
+struct Shared<
+    const INPUT_SZ: usize,
+    const OUTPUT_SZ: usize,
+    E: PopulationEnvironment<INPUT_SZ, OUTPUT_SZ>,
+> {
+    control: Mutex<Control<INPUT_SZ, OUTPUT_SZ, E>>,
+    changed: Condvar,
+}
+
+/// Runs an [`Evaluator`] on its own thread so slow evaluations don't block
+/// the UI. Every generation is sent back as a [`Snapshot`].
+pub struct EvolutionThread<
+    const INPUT_SZ: usize,
+    const OUTPUT_SZ: usize,
+    E: PopulationEnvironment<INPUT_SZ, OUTPUT_SZ>,
+> {
+    shared: Arc<Shared<INPUT_SZ, OUTPUT_SZ, E>>,
+    snapshots: Receiver<Snapshot<INPUT_SZ, OUTPUT_SZ>>,
+}
+
+impl<
+        const INPUT_SZ: usize,
+        const OUTPUT_SZ: usize,
+        E: PopulationEnvironment<INPUT_SZ, OUTPUT_SZ> + Send + 'static,
+    > EvolutionThread<INPUT_SZ, OUTPUT_SZ, E>
+{
+    /// Starts out paused. `ctx` is repainted whenever a snapshot is ready.
+    pub fn spawn(evaluator: Evaluator<INPUT_SZ, OUTPUT_SZ, E>, ctx: egui::Context) -> Self {
+        let shared = Arc::new(Shared {
+            control: Mutex::new(Control {
+                run_mode: RunMode::Paused,
+                updates: Vec::new(),
+                budget: 0,
+                cancelled: false,
+            }),
+            changed: Condvar::new(),
+        });
+        let (sender, snapshots) = channel();
+
+        let thread_shared = shared.clone();
+        thread::spawn(move || run(evaluator, &thread_shared, &sender, &ctx));
+
+        Self { shared, snapshots }
+    }
+
+    pub fn run_mode(&self) -> RunMode {
+        self.shared.control.lock().unwrap().run_mode
+    }
+
+    pub fn set_run_mode(&self, run_mode: RunMode) {
+        self.shared.control.lock().unwrap().run_mode = run_mode;
+        self.shared.changed.notify_one();
+    }
+
+    /// Lets the thread run up to `generations` more generations, replacing
+    /// whatever was left of the last allowance. The UI calls this every
+    /// frame to cap how far evolution gets ahead of it.
+    pub fn allow(&self, generations: usize) {
+        self.shared.control.lock().unwrap().budget = generations;
+        self.shared.changed.notify_one();
+    }
+
+    /// Applies `update` to the evaluator between generations.
+    pub fn update(
+        &self,
+        update: impl FnOnce(&mut Evaluator<INPUT_SZ, OUTPUT_SZ, E>) + Send + 'static,
+    ) {
+        self.shared
+            .control
+            .lock()
+            .unwrap()
+            .updates
+            .push(Box::new(update));
+        self.shared.changed.notify_one();
+    }
+
+    /// Stops the thread once the current generation is done.
+    pub fn cancel(&self) {
+        self.shared.control.lock().unwrap().cancelled = true;
+        self.shared.changed.notify_one();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.control.lock().unwrap().cancelled
+    }
+
+    pub fn try_recv(&self) -> Option<Snapshot<INPUT_SZ, OUTPUT_SZ>> {
+        self.snapshots.try_recv().ok()
+    }
+
+    /// Waits up to `timeout` for the next snapshot.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Snapshot<INPUT_SZ, OUTPUT_SZ>> {
+        self.snapshots.recv_timeout(timeout).ok()
+    }
+}
+
+impl<
+        const INPUT_SZ: usize,
+        const OUTPUT_SZ: usize,
+        E: PopulationEnvironment<INPUT_SZ, OUTPUT_SZ>,
+    > Drop for EvolutionThread<INPUT_SZ, OUTPUT_SZ, E>
+{
+    /// Doesn't wait for the thread, since an evaluation can take arbitrarily
+    /// long. It stops after the current generation.
+    fn drop(&mut self) {
+        self.shared.control.lock().unwrap().cancelled = true;
+        self.shared.changed.notify_one();
+    }
+}
+
+fn run<const INPUT_SZ: usize, const OUTPUT_SZ: usize, E>(
+    mut evaluator: Evaluator<INPUT_SZ, OUTPUT_SZ, E>,
+    shared: &Shared<INPUT_SZ, OUTPUT_SZ, E>,
+    sender: &Sender<Snapshot<INPUT_SZ, OUTPUT_SZ>>,
+    ctx: &egui::Context,
+) where
+    E: PopulationEnvironment<INPUT_SZ, OUTPUT_SZ>,
+{
+    let mut updates_applied = 0;
+
+    loop {
+        let (run_mode, updates) = {
+            let mut control = shared.control.lock().unwrap();
+            while (control.run_mode == RunMode::Paused || control.budget == 0)
+                && control.updates.is_empty()
+                && !control.cancelled
+            {
+                control = shared.changed.wait(control).unwrap();
+            }
+
+            if control.cancelled {
+                return;
+            }
+
+            let run_mode = if control.budget == 0 {
+                RunMode::Paused
+            } else {
+                control.run_mode
+            };
+            if run_mode != RunMode::Paused {
+                control.budget -= 1;
+            }
+
+            (run_mode, take(&mut control.updates))
+        };
+
+        for update in updates {
+            update(&mut evaluator);
+            updates_applied += 1;
+        }
+
+        if run_mode == RunMode::Paused {
+            continue;
+        }
+
+        evaluator.evaluate_and_evolve();
+
+        {
+            let mut control = shared.control.lock().unwrap();
+
+            // Unless it was changed while we were evolving
+            if control.run_mode == run_mode {
+                control.run_mode = match run_mode {
+                    RunMode::Steps(remaining) if remaining > 1 => RunMode::Steps(remaining - 1),
+                    RunMode::Steps(_) => RunMode::Paused,
+                    RunMode::UntilFitness(target)
+                        if evaluator
+                            .champion_fitness
+                            .is_some_and(|fitness| fitness >= target) =>
+                    {
+                        RunMode::Paused
+                    }
+                    run_mode => run_mode,
+                };
+            }
+        }
+
+        let snapshot = Snapshot {
+            islands: evaluator
+                .islands
+                .iter()
+                .map(|island| Generation {
+                    population: island.population.clone(),
+                    speciation: island.last_speciation.clone().unwrap(),
+                    evaluation: island.last_evaluation.clone().unwrap(),
+                })
+                .collect(),
+            lineage: evaluator.lineage.take_changes(),
+            updates_applied,
+        };
+
+        if sender.send(snapshot).is_err() {
+            return;
+        }
+        ctx.request_repaint();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xor::XOREnv;
+
+    #[test]
+    fn test_steps() {
+        let thread = EvolutionThread::spawn(Evaluator::new(XOREnv, 20), egui::Context::default());
+        thread.allow(usize::MAX);
+        thread.set_run_mode(RunMode::Steps(3));
+
+        let mut lineage = Lineage::default();
+        for generation in 1..=3 {
+            let snapshot = thread
+                .recv_timeout(Duration::from_secs(10))
+                .expect("Generation wasn't sent");
+            assert_eq!(snapshot.islands[0].population.generation, generation);
+            lineage.merge(snapshot.lineage);
+        }
+
+        // The thread pauses before sending the last step, so nothing can
+        // follow it
+        assert_eq!(thread.run_mode(), RunMode::Paused);
+        assert!(thread.try_recv().is_none());
+        assert!(lineage.get(0).is_some());
+    }
+}
@@ -0,0 +1,625 @@
+use std::{fmt::Write, fs, io, path::Path};
+
+use eframe::{
+    egui::{self, Align, Align2, FontDefinitions, FontId, Painter, TextStyle},
+    epaint::{pos2, text::Fonts, Color32, ImageData, Pos2, Rect, Shape, Stroke, Vec2},
+};
+use tiny_skia::{
+    FillRule, Paint, PathBuilder, Pixmap, PremultipliedColorU8, StrokeDash, Transform,
+};
+
+/// The font atlas is only used once, so it can be as large as it likes.
+const MAX_TEXTURE_SIDE: usize = 8192;
+const PLOT_MARGIN: f32 = 40.0;
+const PLOT_TICKS: usize = 5;
+
+/// Something drawn in a [`Scene`], in pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Primitive {
+    Line {
+        points: Vec<Pos2>,
+        stroke: Stroke,
+        /// The length of the dashes and the gaps between them, if dashed.
+        dash: Option<f32>,
+    },
+    /// Has to be convex.
+    Polygon { points: Vec<Pos2>, fill: Color32 },
+    Circle {
+        center: Pos2,
+        radius: f32,
+        fill: Color32,
+        stroke: Stroke,
+    },
+    Text {
+        pos: Pos2,
+        anchor: Align2,
+        text: String,
+        font: FontId,
+        color: Color32,
+    },
+}
+
+impl Primitive {
+    pub fn paint(&self, painter: &Painter) {
+        match self {
+            Primitive::Line {
+                points,
+                stroke,
+                dash: Some(dash),
+            } => {
+                painter.extend(Shape::dashed_line(points, *stroke, *dash, *dash));
+            }
+            Primitive::Line {
+                points,
+                stroke,
+                dash: None,
+            } => {
+                painter.add(Shape::line(points.clone(), *stroke));
+            }
+            Primitive::Polygon { points, fill } => {
+                painter.add(Shape::convex_polygon(points.clone(), *fill, Stroke::NONE));
+            }
+            Primitive::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => {
+                painter.circle(*center, *radius, *fill, *stroke);
+            }
+            Primitive::Text {
+                pos,
+                anchor,
+                text,
+                font,
+                color,
+            } => {
+                painter.text(*pos, *anchor, text, font.clone(), *color);
+            }
+        }
+    }
+}
+
+/// A picture that can be painted in the UI or saved for use elsewhere.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub size: Vec2,
+    pub background: Color32,
+    pub primitives: Vec<Primitive>,
+}
+
+fn svg_paint(attribute: &str, color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+
+    format!(
+        r#"{attribute}="rgb({r},{g},{b})" {attribute}-opacity="{:.3}""#,
+        a as f32 / 255.0
+    )
+}
+
+fn svg_points(points: &[Pos2]) -> String {
+    points
+        .iter()
+        .map(|point| format!("{},{}", point.x, point.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn svg_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn skia_paint(color: Color32) -> Paint<'static> {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(r, g, b, a);
+    paint.anti_alias = true;
+    paint
+}
+
+fn skia_stroke(stroke: Stroke, dash: Option<f32>) -> tiny_skia::Stroke {
+    tiny_skia::Stroke {
+        width: stroke.width,
+        dash: dash.and_then(|dash| StrokeDash::new(vec![dash, dash], 0.0)),
+        ..Default::default()
+    }
+}
+
+fn skia_path(points: &[Pos2]) -> Option<tiny_skia::Path> {
+    let (first, rest) = points.split_first()?;
+    let mut builder = PathBuilder::new();
+
+    builder.move_to(first.x, first.y);
+    for point in rest {
+        builder.line_to(point.x, point.y);
+    }
+
+    builder.finish()
+}
+
+/// Blends `color` over a pixel, `coverage` of the way.
+fn blend(pixel: &mut PremultipliedColorU8, color: Color32, coverage: f32) {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let alpha = a as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+    let mix = |src: u8, dst: u8| (src as f32 * alpha + dst as f32 * (1.0 - alpha)).round() as u8;
+
+    let alpha_out = mix(255, pixel.alpha());
+    *pixel = PremultipliedColorU8::from_rgba(
+        mix(r, pixel.red()).min(alpha_out),
+        mix(g, pixel.green()).min(alpha_out),
+        mix(b, pixel.blue()).min(alpha_out),
+        alpha_out,
+    )
+    .expect("Channels are clamped to alpha");
+}
+
+impl Scene {
+    pub fn new(size: Vec2, background: Color32) -> Self {
+        Self {
+            size,
+            background,
+            primitives: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, primitive: Primitive) {
+        self.primitives.push(primitive);
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = self.size.x,
+            h = self.size.y,
+        );
+        svg.push('\n');
+        writeln!(
+            svg,
+            r#"<rect width="100%" height="100%" {}/>"#,
+            svg_paint("fill", self.background)
+        )
+        .unwrap();
+
+        for primitive in &self.primitives {
+            match primitive {
+                Primitive::Line {
+                    points,
+                    stroke,
+                    dash,
+                } => {
+                    write!(
+                        svg,
+                        r#"<polyline points="{}" fill="none" {} stroke-width="{}""#,
+                        svg_points(points),
+                        svg_paint("stroke", stroke.color),
+                        stroke.width
+                    )
+                    .unwrap();
+                    if let Some(dash) = dash {
+                        write!(svg, r#" stroke-dasharray="{dash} {dash}""#).unwrap();
+                    }
+                    svg.push_str("/>\n");
+                }
+                Primitive::Polygon { points, fill } => {
+                    writeln!(
+                        svg,
+                        r#"<polygon points="{}" {}/>"#,
+                        svg_points(points),
+                        svg_paint("fill", *fill)
+                    )
+                    .unwrap();
+                }
+                Primitive::Circle {
+                    center,
+                    radius,
+                    fill,
+                    stroke,
+                } => {
+                    writeln!(
+                        svg,
+                        r#"<circle cx="{}" cy="{}" r="{radius}" {} {} stroke-width="{}"/>"#,
+                        center.x,
+                        center.y,
+                        svg_paint("fill", *fill),
+                        svg_paint("stroke", stroke.color),
+                        stroke.width
+                    )
+                    .unwrap();
+                }
+                Primitive::Text {
+                    pos,
+                    anchor,
+                    text,
+                    font,
+                    color,
+                } => {
+                    let text_anchor = match anchor.x() {
+                        Align::Min => "start",
+                        Align::Center => "middle",
+                        Align::Max => "end",
+                    };
+                    let baseline = match anchor.y() {
+                        Align::Min => "hanging",
+                        Align::Center => "middle",
+                        Align::Max => "text-after-edge",
+                    };
+
+                    writeln!(
+                        svg,
+                        r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{}" text-anchor="{text_anchor}" dominant-baseline="{baseline}" xml:space="preserve" {}>{}</text>"#,
+                        pos.x,
+                        pos.y,
+                        font.size,
+                        svg_paint("fill", *color),
+                        svg_escape(text)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Rasterizes the scene on the CPU, with text laid out by egui's
+    /// default fonts. Fails if the scene is less than a pixel in size, e.g.
+    /// because its panel was collapsed.
+    pub fn to_png(&self) -> io::Result<Vec<u8>> {
+        let mut pixmap = Pixmap::new(self.size.x.ceil() as u32, self.size.y.ceil() as u32)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "The scene has to be at least a pixel in size",
+                )
+            })?;
+        let [r, g, b, a] = self.background.to_srgba_unmultiplied();
+        pixmap.fill(tiny_skia::Color::from_rgba8(r, g, b, a));
+
+        let fonts = Fonts::new(1.0, MAX_TEXTURE_SIDE, FontDefinitions::default());
+        let mut texts = Vec::new();
+
+        for primitive in &self.primitives {
+            match primitive {
+                Primitive::Line {
+                    points,
+                    stroke,
+                    dash,
+                } => {
+                    if let Some(path) = skia_path(points) {
+                        pixmap.stroke_path(
+                            &path,
+                            &skia_paint(stroke.color),
+                            &skia_stroke(*stroke, *dash),
+                            Transform::identity(),
+                            None,
+                        );
+                    }
+                }
+                Primitive::Polygon { points, fill } => {
+                    if let Some(path) = skia_path(points) {
+                        pixmap.fill_path(
+                            &path,
+                            &skia_paint(*fill),
+                            FillRule::Winding,
+                            Transform::identity(),
+                            None,
+                        );
+                    }
+                }
+                Primitive::Circle {
+                    center,
+                    radius,
+                    fill,
+                    stroke,
+                } => {
+                    if let Some(path) = PathBuilder::from_circle(center.x, center.y, *radius) {
+                        pixmap.fill_path(
+                            &path,
+                            &skia_paint(*fill),
+                            FillRule::Winding,
+                            Transform::identity(),
+                            None,
+                        );
+                        if stroke.width > 0.0 {
+                            pixmap.stroke_path(
+                                &path,
+                                &skia_paint(stroke.color),
+                                &skia_stroke(*stroke, None),
+                                Transform::identity(),
+                                None,
+                            );
+                        }
+                    }
+                }
+                Primitive::Text {
+                    pos,
+                    anchor,
+                    text,
+                    font,
+                    color,
+                } => {
+                    let galley = fonts.layout_no_wrap(text.clone(), font.clone(), *color);
+                    let rect = anchor.anchor_rect(Rect::from_min_size(*pos, galley.size()));
+                    texts.push((rect.min, galley, *color));
+                }
+            }
+        }
+
+        // Text goes on top since the glyphs have to be laid out before the
+        // font atlas is complete
+        if let Some(ImageData::Font(atlas)) = fonts.font_image_delta().map(|delta| delta.image) {
+            let width = pixmap.width() as i32;
+            let height = pixmap.height() as i32;
+            let pixels = pixmap.pixels_mut();
+
+            for (min, galley, color) in texts {
+                for row in &galley.rows {
+                    for glyph in &row.glyphs {
+                        let glyph_min = (min + glyph.pos.to_vec2() + glyph.uv_rect.offset).round();
+                        let [min_u, min_v] = glyph.uv_rect.min.map(usize::from);
+                        let [max_u, max_v] = glyph.uv_rect.max.map(usize::from);
+
+                        for v in min_v..max_v {
+                            for u in min_u..max_u {
+                                let x = glyph_min.x as i32 + (u - min_u) as i32;
+                                let y = glyph_min.y as i32 + (v - min_v) as i32;
+                                if (0..width).contains(&x) && (0..height).contains(&y) {
+                                    blend(
+                                        &mut pixels[(y * width + x) as usize],
+                                        color,
+                                        atlas.pixels[v * atlas.size[0] + u],
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(pixmap
+            .encode_png()
+            .expect("Encoding a PNG in memory shouldn't fail"))
+    }
+
+    /// Writes an SVG or a PNG depending on the extension of `path`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("svg") => fs::write(path, self.to_svg()),
+            Some("png") => fs::write(path, self.to_png()?),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Exports have to end in .svg or .png",
+            )),
+        }
+    }
+}
+
+/// A line of a [`line_plot`].
+pub struct Series {
+    pub name: String,
+    pub color: Color32,
+    pub points: Vec<[f64; 2]>,
+}
+
+/// Draws `series` in a frame with ticks on both axes and a legend in the
+/// top left, with vertical lines at `markers`.
+pub fn line_plot(size: Vec2, style: &egui::Style, series: &[Series], markers: &[f64]) -> Scene {
+    let visuals = &style.visuals;
+    let text_color = visuals.text_color();
+    let font = TextStyle::Small.resolve(style);
+    let mut scene = Scene::new(size, visuals.extreme_bg_color);
+
+    let frame = Rect::from_min_max(
+        pos2(PLOT_MARGIN, PLOT_MARGIN / 2.0),
+        pos2(size.x - PLOT_MARGIN / 2.0, size.y - PLOT_MARGIN),
+    );
+    let points = series.iter().flat_map(|series| &series.points);
+    let (min_x, max_x) = points
+        .clone()
+        .map(|[x, _]| *x)
+        .chain(markers.iter().copied())
+        .fold((f64::MAX, f64::MIN), |(min, max), x| {
+            (min.min(x), max.max(x))
+        });
+    let (min_y, max_y) = points
+        .map(|[_, y]| *y)
+        .fold((f64::MAX, f64::MIN), |(min, max), y| {
+            (min.min(y), max.max(y))
+        });
+    // Without anything to draw, or all of it in a line
+    let (min_x, max_x) = if min_x < max_x {
+        (min_x, max_x)
+    } else {
+        (min_x.min(0.0), min_x.max(0.0) + 1.0)
+    };
+    let (min_y, max_y) = if min_y < max_y {
+        (min_y, max_y)
+    } else {
+        (min_y.min(0.0), min_y.max(0.0) + 1.0)
+    };
+
+    let to_screen = |x: f64, y: f64| {
+        pos2(
+            frame.left() + ((x - min_x) / (max_x - min_x)) as f32 * frame.width(),
+            frame.bottom() - ((y - min_y) / (max_y - min_y)) as f32 * frame.height(),
+        )
+    };
+
+    for tick in 0..PLOT_TICKS {
+        let t = tick as f64 / (PLOT_TICKS - 1) as f64;
+        let x = min_x + (max_x - min_x) * t;
+        let y = min_y + (max_y - min_y) * t;
+
+        scene.push(Primitive::Line {
+            points: vec![to_screen(min_x, y), to_screen(max_x, y)],
+            stroke: visuals.widgets.noninteractive.bg_stroke,
+            dash: None,
+        });
+        scene.push(Primitive::Text {
+            pos: to_screen(x, min_y) + Vec2::new(0.0, 4.0),
+            anchor: Align2::CENTER_TOP,
+            text: format!("{:.1}", x),
+            font: font.clone(),
+            color: text_color,
+        });
+        scene.push(Primitive::Text {
+            pos: to_screen(min_x, y) - Vec2::new(4.0, 0.0),
+            anchor: Align2::RIGHT_CENTER,
+            text: format!("{:.2}", y),
+            font: font.clone(),
+            color: text_color,
+        });
+    }
+
+    for &marker in markers {
+        scene.push(Primitive::Line {
+            points: vec![to_screen(marker, min_y), to_screen(marker, max_y)],
+            stroke: Stroke::new(1.0, Color32::GRAY),
+            dash: None,
+        });
+    }
+
+    for series in series {
+        scene.push(Primitive::Line {
+            points: series
+                .points
+                .iter()
+                .map(|&[x, y]| to_screen(x, y))
+                .collect(),
+            stroke: Stroke::new(1.5, series.color),
+            dash: None,
+        });
+    }
+
+    let row_height = font.size * 1.5;
+    for (index, series) in series.iter().enumerate() {
+        let pos = frame.left_top() + Vec2::new(8.0, 8.0 + row_height * (index as f32 + 0.5));
+
+        scene.push(Primitive::Circle {
+            center: pos,
+            radius: font.size / 3.0,
+            fill: series.color,
+            stroke: Stroke::NONE,
+        });
+        scene.push(Primitive::Text {
+            pos: pos + Vec2::new(font.size, 0.0),
+            anchor: Align2::LEFT_CENTER,
+            text: series.name.clone(),
+            font: font.clone(),
+            color: text_color,
+        });
+    }
+
+    scene.push(Primitive::Line {
+        points: vec![
+            frame.left_top(),
+            frame.right_top(),
+            frame.right_bottom(),
+            frame.left_bottom(),
+            frame.left_top(),
+        ],
+        stroke: visuals.widgets.noninteractive.bg_stroke,
+        dash: None,
+    });
+
+    scene
+}
+
+/// A menu for saving a [`Scene`] to a path typed in by the user.
+pub struct ExportMenu {
+    path: String,
+    /// What happened the last time something was saved.
+    status: Option<String>,
+}
+
+impl ExportMenu {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_owned(),
+            status: None,
+        }
+    }
+
+    /// Returns whether the scene should be saved with [`ExportMenu::save`].
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        ui.menu_button("Export", |ui| {
+            ui.label("Path (.svg or .png)");
+            ui.text_edit_singleline(&mut self.path);
+            let save = ui.button("Save").clicked();
+
+            if let Some(status) = &self.status {
+                ui.label(status);
+            }
+
+            save
+        })
+        .inner
+        .unwrap_or(false)
+    }
+
+    pub fn save(&mut self, scene: &Scene) {
+        self.status = Some(match scene.save(Path::new(&self.path)) {
+            Ok(()) => format!("Saved {}", self.path),
+            Err(error) => format!("Couldn't save {}: {}", self.path, error),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene() -> Scene {
+        let mut scene = Scene::new(Vec2::new(40.0, 20.0), Color32::BLACK);
+        scene.push(Primitive::Circle {
+            center: pos2(10.0, 10.0),
+            radius: 5.0,
+            fill: Color32::RED,
+            stroke: Stroke::NONE,
+        });
+        scene.push(Primitive::Text {
+            pos: pos2(20.0, 10.0),
+            anchor: Align2::LEFT_CENTER,
+            text: "a<b".to_owned(),
+            font: FontId::proportional(12.0),
+            color: Color32::WHITE,
+        });
+        scene
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = scene().to_svg();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r#"<circle cx="10" cy="10" r="5" fill="rgb(255,0,0)""#));
+        assert!(svg.contains(">a&lt;b</text>"));
+    }
+
+    #[test]
+    fn test_png() {
+        let png = scene().to_png().unwrap();
+        let pixmap = Pixmap::decode_png(&png).unwrap();
+        let pixel = |x: u32, y: u32| pixmap.pixel(x, y).unwrap();
+
+        assert_eq!((pixmap.width(), pixmap.height()), (40, 20));
+        assert_eq!(pixel(10, 10).red(), 255);
+        assert_eq!(pixel(1, 1).red(), 0);
+        // Some of the text got drawn
+        assert!((20..40).any(|x| (0..20).any(|y| pixel(x, y).green() > 0)));
+    }
+
+    #[test]
+    fn test_empty_png() {
+        let scene = Scene::new(Vec2::new(0.0, 20.0), Color32::BLACK);
+
+        assert_eq!(
+            scene.to_png().unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
@@ -1,10 +1,23 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
 use eframe::egui::*;
 use rand::random;
 
-use crate::node::Node;
+use crate::{
+    export::{ExportMenu, Primitive, Scene},
+    quadtree::QuadTree,
+};
 
 const METERS2PIXELS: f32 = 7.0;
-const PIXELS2METERS: f32 = 1.0 / METERS2PIXELS;
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 100.0;
+/// How much one pixel of scrolling zooms in.
+const SCROLL_ZOOM_SPEED: f32 = 0.002;
+/// Kept around the nodes when fitting them into view, in meters.
+const FIT_MARGIN: f32 = NODE_SIZE * 4.0;
 const NODE_SIZE: f32 = 1.0;
 const SPAWN_SIZE: f32 = 5.0;
 const IDEAL_DIST: f32 = NODE_SIZE * 2.0;
@@ -15,9 +28,25 @@ const MASS: f32 = 0.125;
 const FRICTION: f32 = 0.8;
 const MAX_FORCE: f32 = 100.0;
 const MAX_SPEED: f32 = 50.0;
+/// How coarse the Barnes-Hut approximation of the repulsion is, see
+/// [`QuadTree::repulsion`].
+const THETA: f32 = 0.7;
+/// The layout counts as settled once the average kinetic energy of the
+/// nodes drops below this.
+const SETTLED_ENERGY: f32 = 0.01;
 
 const NODE_INTERACTION_SIZE: f32 = NODE_SIZE * 2.0;
 
+/// How far the middle of a curved edge bends away from the straight line,
+/// relative to its length.
+const CURVE_BEND: f32 = 0.2;
+const CURVE_SEGMENTS: usize = 16;
+const LOOP_OFFSET: f32 = NODE_SIZE * 1.5;
+const LOOP_RADIUS: f32 = NODE_SIZE * 0.9;
+/// In pixels, on top of the edge's width.
+const ARROW_SIZE: f32 = 6.0;
+const DASH_LENGTH: f32 = 4.0;
+
 #[derive(Debug, Default)]
 pub struct NodeEntity {
     pub pos: Pos2,
@@ -58,40 +87,215 @@ impl NodeEntity {
     }
 }
 
-pub trait Graph<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
-    fn connected(
-        &self,
-        node_1: Node<INPUT_SZ, OUTPUT_SZ>,
-        node_2: Node<INPUT_SZ, OUTPUT_SZ>,
-    ) -> bool;
+/// How a line between two nodes is drawn. Unset fields fall back to the UI
+/// style.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EdgeStyle {
+    pub color: Option<Color32>,
+    /// In pixels.
+    pub width: Option<f32>,
+    pub dashed: bool,
+}
 
-    fn size(&self) -> usize;
+/// How a node is drawn. Unset fields fall back to the UI style.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NodeStyle {
+    pub color: Option<Color32>,
+}
 
-    fn connection_text(
-        &self,
-        _node_1: Node<INPUT_SZ, OUTPUT_SZ>,
-        _node_2: Node<INPUT_SZ, OUTPUT_SZ>,
-    ) -> Option<String> {
+/// Something [`FDGraph`] can lay out and draw.
+pub trait Graph {
+    /// Identifies a node of the graph.
+    type Vertex: Copy + Eq + Hash;
+
+    /// Every node, in the order they're drawn.
+    fn vertices(&self) -> Vec<Self::Vertex>;
+
+    /// The lines that pull their nodes together. In directed graphs they go
+    /// from the first node to the second, otherwise every pair should only
+    /// be listed once.
+    fn edges(&self) -> Vec<(Self::Vertex, Self::Vertex)>;
+
+    /// Lines that are drawn like [`Graph::edges`] but don't pull on their
+    /// nodes.
+    fn loose_edges(&self) -> Vec<(Self::Vertex, Self::Vertex)> {
+        Vec::new()
+    }
+
+    fn connection_text(&self, _node_1: Self::Vertex, _node_2: Self::Vertex) -> Option<String> {
         None
     }
 
-    fn node_text(&self, _node: Node<INPUT_SZ, OUTPUT_SZ>) -> Option<String> {
+    fn node_text(&self, _node: Self::Vertex) -> Option<String> {
         None
     }
+
+    /// How to draw the edge from `node_1` to `node_2`, or `None` to not draw
+    /// it.
+    fn connection_style(&self, _node_1: Self::Vertex, _node_2: Self::Vertex) -> Option<EdgeStyle> {
+        Some(EdgeStyle::default())
+    }
+
+    fn node_style(&self, _node: Self::Vertex) -> NodeStyle {
+        NodeStyle::default()
+    }
+
+    /// Whether the lines from [`Graph::connection_style`] end in an
+    /// arrowhead. A pair with lines both ways is drawn as two curves, and a line from
+    /// a node to itself as a loop.
+    fn directed(&self) -> bool {
+        false
+    }
 }
 
-#[derive(Default)]
-pub struct FDGraph<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
-    entities: Vec<NodeEntity>,
+/// Samples the quadratic Bézier curve from `from` to `to` through
+/// `control`.
+fn bezier(from: Pos2, control: Pos2, to: Pos2) -> Vec<Pos2> {
+    (0..=CURVE_SEGMENTS)
+        .map(|segment| {
+            let t = segment as f32 / CURVE_SEGMENTS as f32;
+            let point = from.to_vec2() * (1.0 - t) * (1.0 - t)
+                + control.to_vec2() * 2.0 * (1.0 - t) * t
+                + to.to_vec2() * t * t;
+            point.to_pos2()
+        })
+        .collect()
+}
+
+/// The points of an edge and where to put its label. The path ends on the
+/// edge of the `to` node, so there's room for an arrowhead, unless the nodes
+/// overlap, in which case it's just the line between their centers.
+fn edge_path(from: Pos2, to: Pos2, curved: bool) -> (Vec<Pos2>, Pos2) {
+    if from.distance(to) < NODE_SIZE {
+        return (vec![from, to], from + (to - from) / 2.0);
+    }
+
+    let dir = (to - from).normalized();
+
+    if !curved {
+        return (vec![from, to - dir * NODE_SIZE], from + (to - from) / 2.0);
+    }
+
+    // Each direction of a pair bends to its own left, so they separate
+    let control = from + (to - from) / 2.0 + dir.rot90() * from.distance(to) * CURVE_BEND;
+    let mut path = bezier(from, control, to);
+    path.retain(|point| point.distance(to) >= NODE_SIZE);
+
+    (path, bezier(from, control, to)[CURVE_SEGMENTS / 2])
+}
+
+/// A loop above the node, from where it leaves the node to where it comes
+/// back in on the right.
+fn loop_path(pos: Pos2) -> (Vec<Pos2>, Pos2) {
+    let center = pos - vec2(0.0, LOOP_OFFSET);
+
+    // Where the loop crosses the node, along and across the axis between
+    // their centers
+    let along = (LOOP_OFFSET * LOOP_OFFSET + NODE_SIZE * NODE_SIZE - LOOP_RADIUS * LOOP_RADIUS)
+        / (2.0 * LOOP_OFFSET);
+    let across = (NODE_SIZE * NODE_SIZE - along * along).sqrt();
+    let end_angle = (LOOP_OFFSET - along).atan2(across);
+    let start_angle = std::f32::consts::PI - end_angle;
+    let sweep = std::f32::consts::TAU + end_angle - start_angle;
+
+    let path = (0..=CURVE_SEGMENTS)
+        .map(|segment| {
+            let angle = start_angle + sweep * segment as f32 / CURVE_SEGMENTS as f32;
+            center + Vec2::angled(angle) * LOOP_RADIUS
+        })
+        .collect();
+
+    (path, center - vec2(0.0, LOOP_RADIUS))
+}
+
+/// Which part of the world an [`FDGraph`] shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// The world position in the middle of the view.
+    pub center: Pos2,
+    /// Pixels per meter.
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            center: Pos2::ZERO,
+            zoom: METERS2PIXELS,
+        }
+    }
+}
+
+impl Camera {
+    /// The part of the world shown on `screen`.
+    pub fn view(&self, screen: Rect) -> Rect {
+        Rect::from_center_size(self.center, screen.size() / self.zoom)
+    }
+
+    /// Zooms by `factor` while keeping `anchor` in the same place on screen.
+    pub fn zoom_at(&mut self, factor: f32, anchor: Pos2) {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+
+        self.center = anchor + (self.center - anchor) * (self.zoom / zoom);
+        self.zoom = zoom;
+    }
+
+    /// Shows all of `bounds` on a screen of `screen_size`.
+    pub fn fit(&mut self, bounds: Rect, screen_size: Vec2) {
+        let bounds = bounds.expand(FIT_MARGIN);
+
+        self.center = bounds.center();
+        self.zoom = (screen_size.x / bounds.width())
+            .min(screen_size.y / bounds.height())
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
+
+type Spawner<V> = Box<dyn FnMut(V) -> Option<NodeEntity>>;
+
+/// A force-directed layout of any [`Graph`] with vertices of type `V`.
+pub struct FDGraph<V> {
+    entities: HashMap<V, NodeEntity>,
     draw_lines: bool,
-    spawner: Option<Box<dyn FnMut(Node<INPUT_SZ, OUTPUT_SZ>) -> Option<NodeEntity>>>,
+    spawner: Option<Spawner<V>>,
+    pub camera: Camera,
+    /// Whether nodes are kept inside the view.
+    pub clamped: bool,
+    /// Fit every node into view the next time the graph is shown, since
+    /// that needs the size of the screen.
+    fit_requested: bool,
+    /// Stops the simulation, nodes only move when dragged.
+    pub frozen: bool,
+    /// Whether the layout stopped moving, so there's no need to simulate or
+    /// repaint until something changes.
+    settled: bool,
+    /// The vertices and edges of the graph when it was last shown, to notice
+    /// when it changes.
+    last_graph: (Vec<V>, Vec<(V, V)>),
+    export_menu: ExportMenu,
+}
+
+impl<V> Default for FDGraph<V> {
+    fn default() -> Self {
+        Self {
+            entities: HashMap::new(),
+            draw_lines: false,
+            spawner: None,
+            camera: Camera::default(),
+            clamped: false,
+            fit_requested: false,
+            frozen: false,
+            settled: false,
+            last_graph: (Vec::new(), Vec::new()),
+            export_menu: ExportMenu::new("graph.svg"),
+        }
+    }
 }
 
-impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> FDGraph<INPUT_SZ, OUTPUT_SZ> {
-    pub fn with_spawner(
-        spawner: Box<dyn FnMut(Node<INPUT_SZ, OUTPUT_SZ>) -> Option<NodeEntity>>,
-        draw_lines: bool,
-    ) -> Self {
+impl<V: Copy + Eq + Hash> FDGraph<V> {
+    /// `spawner` places new nodes, or leaves them to be spawned randomly by
+    /// returning `None`.
+    pub fn with_spawner(spawner: Spawner<V>, draw_lines: bool) -> Self {
         Self {
             spawner: Some(spawner),
             draw_lines,
@@ -99,55 +303,102 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> FDGraph<INPUT_SZ, OUTPUT_SZ>
         }
     }
 
-    fn update(&mut self, dt: f32, graph: &impl Graph<INPUT_SZ, OUTPUT_SZ>, space: Rect) {
-        self.entities.truncate(graph.size());
-        for i in self.entities.len()..graph.size() {
-            self.entities.push(
-                self.spawner
-                    .as_mut()
-                    .and_then(|spawner| spawner(Node(i)))
-                    .unwrap_or_else(|| NodeEntity::spawn()),
-            );
+    /// Zooms and pans so every node is in view.
+    pub fn fit_all(&mut self) {
+        self.fit_requested = true;
+    }
+
+    pub fn center_on(&mut self, node: V) {
+        if let Some(entity) = self.entities.get(&node) {
+            self.camera.center = entity.pos;
         }
+    }
 
-        for i in 0..graph.size() {
-            for j in 0..graph.size() {
-                if i == j {
-                    continue;
-                }
+    /// Where `node` currently is.
+    pub fn position(&self, node: V) -> Option<Pos2> {
+        self.entities.get(&node).map(|entity| entity.pos)
+    }
 
-                let other_pos = self.entities[i].pos;
-                let this = &mut self.entities[j];
+    /// Starts simulating again after the layout settled.
+    pub fn wake(&mut self) {
+        self.settled = false;
+    }
 
-                let dist = this.pos.distance(other_pos);
-                if dist == 0.0 {
-                    continue;
-                }
-                let mut force = 0.0;
+    /// Spawns and simulates unless the layout is frozen or settled.
+    fn step(&mut self, dt: f32, graph: &impl Graph<Vertex = V>, space: Rect) {
+        let vertices = graph.vertices();
+        let present = vertices.iter().collect::<HashSet<_>>();
+        self.entities.retain(|node, _| present.contains(node));
+        for &node in &vertices {
+            if !self.entities.contains_key(&node) {
+                let entity = self
+                    .spawner
+                    .as_mut()
+                    .and_then(|spawner| spawner(node))
+                    .unwrap_or_else(NodeEntity::spawn);
+                self.entities.insert(node, entity);
+            }
+        }
+
+        let edges = graph.edges();
+        if self.last_graph.0 != vertices || self.last_graph.1 != edges {
+            self.wake();
+        }
 
-                force -= REPLUSION_FORCE / (dist * dist);
+        if !self.frozen && !self.settled {
+            let energy = self.update(dt, &vertices, &edges, space);
+            self.settled = energy <= SETTLED_ENERGY * vertices.len() as f32;
+        }
 
-                if graph.connected(Node(i), Node(j)) {
-                    let ideal_dist = dist - IDEAL_DIST;
+        self.last_graph = (vertices, edges);
+    }
 
-                    force += ATTRACTION_FORCE * ideal_dist;
-                }
+    /// Returns the kinetic energy of the nodes afterwards.
+    fn update(&mut self, dt: f32, vertices: &[V], edges: &[(V, V)], space: Rect) -> f32 {
+        let tree = QuadTree::new(
+            &vertices
+                .iter()
+                .map(|node| self.entities[node].pos)
+                .collect::<Vec<_>>(),
+        );
+        for entity in self.entities.values_mut() {
+            entity.force += tree.repulsion(entity.pos, REPLUSION_FORCE, THETA);
+        }
 
-                this.force += (other_pos - this.pos).normalized() * force;
+        for (node_1, node_2) in edges {
+            let (Some(pos_1), Some(pos_2)) = (self.position(*node_1), self.position(*node_2))
+            else {
+                continue;
+            };
+            let dist = pos_1.distance(pos_2);
+            if node_1 == node_2 || dist == 0.0 {
+                continue;
             }
+            let force = (pos_2 - pos_1).normalized() * ATTRACTION_FORCE * (dist - IDEAL_DIST);
+
+            self.entities.get_mut(node_1).unwrap().force += force;
+            self.entities.get_mut(node_2).unwrap().force -= force;
         }
 
         // dbg!(self.entities.first());
 
         // let space_size = space.size();
-        for entity in &mut self.entities {
+        for entity in self.entities.values_mut() {
             entity.force += (Pos2::ZERO - entity.pos).normalized() * GRAVITY;
 
             entity.update(dt);
 
-            entity.pos = space.clamp(entity.pos);
+            if self.clamped {
+                entity.pos = space.clamp(entity.pos);
+            }
         }
 
+        let energy = self
+            .entities
+            .values()
+            .map(|entity| 0.5 * MASS * entity.vel.length_sq())
+            .sum();
+
         // let space_size = space.size();
 
         // for entity in &mut self.entities {
@@ -167,93 +418,372 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> FDGraph<INPUT_SZ, OUTPUT_SZ>
         //
         //     entity.update(dt);
         // }
+
+        energy
+    }
+
+    /// The edges as they're drawn with `to_screen`.
+    fn edge_primitives(
+        &self,
+        graph: &impl Graph<Vertex = V>,
+        to_screen: &emath::RectTransform,
+        style: &Style,
+    ) -> Vec<Primitive> {
+        let edges = graph
+            .edges()
+            .into_iter()
+            .chain(graph.loose_edges())
+            .filter_map(|(from, to)| Some((from, to, graph.connection_style(from, to)?)))
+            .collect::<Vec<_>>();
+        let drawn = edges
+            .iter()
+            .map(|&(from, to, _)| (from, to))
+            .collect::<HashSet<_>>();
+        let visuals = &style.visuals.widgets.noninteractive;
+        let mut primitives = Vec::new();
+
+        for (from, to, edge_style) in edges {
+            let (Some(from_pos), Some(to_pos)) = (self.position(from), self.position(to)) else {
+                continue;
+            };
+            let stroke = Stroke::new(
+                edge_style.width.unwrap_or(visuals.fg_stroke.width),
+                edge_style.color.unwrap_or(visuals.fg_stroke.color),
+            );
+
+            let (path, label_pos) = if from == to {
+                loop_path(from_pos)
+            } else {
+                let curved = graph.directed() && drawn.contains(&(to, from));
+                edge_path(from_pos, to_pos, curved)
+            };
+            let path = path
+                .into_iter()
+                .map(|point| *to_screen * point)
+                .collect::<Vec<_>>();
+
+            let arrow = match path[..] {
+                [.., before_tip, tip] if graph.directed() && before_tip != tip => {
+                    let dir = (tip - before_tip).normalized();
+                    let size = ARROW_SIZE + stroke.width;
+
+                    Some(Primitive::Polygon {
+                        points: vec![
+                            tip,
+                            tip - dir * size + dir.rot90() * size / 2.0,
+                            tip - dir * size - dir.rot90() * size / 2.0,
+                        ],
+                        fill: stroke.color,
+                    })
+                }
+                _ => None,
+            };
+
+            primitives.push(Primitive::Line {
+                points: path,
+                stroke,
+                dash: edge_style.dashed.then_some(DASH_LENGTH),
+            });
+            primitives.extend(arrow);
+
+            if let Some(text) = graph.connection_text(from, to) {
+                primitives.push(Primitive::Text {
+                    pos: *to_screen * label_pos,
+                    anchor: Align2::LEFT_CENTER,
+                    text: " ".to_string() + &text,
+                    font: TextStyle::Small.resolve(style),
+                    color: visuals.text_color(),
+                });
+            }
+        }
+
+        primitives
+    }
+
+    /// `node` as it's drawn with `to_screen`.
+    fn node_primitives(
+        &self,
+        graph: &impl Graph<Vertex = V>,
+        node: V,
+        to_screen: &emath::RectTransform,
+        style: &Style,
+        visuals: &style::WidgetVisuals,
+    ) -> Vec<Primitive> {
+        let Some(pos) = self.position(node) else {
+            return Vec::new();
+        };
+        let mut primitives = vec![Primitive::Circle {
+            center: *to_screen * pos,
+            radius: NODE_SIZE * self.camera.zoom,
+            fill: graph.node_style(node).color.unwrap_or(visuals.bg_fill),
+            stroke: visuals.bg_stroke,
+        }];
+
+        if let Some(text) = graph.node_text(node) {
+            primitives.push(Primitive::Text {
+                pos: *to_screen * pos2(pos.x + 1.0, pos.y),
+                anchor: Align2::LEFT_CENTER,
+                text: " ".to_string() + &text,
+                font: TextStyle::Small.resolve(style),
+                color: visuals.text_color(),
+            });
+        }
+
+        primitives
+    }
+
+    /// The graph as it's currently shown on a screen of `size`, without
+    /// anything hovered.
+    pub fn scene(&self, graph: &impl Graph<Vertex = V>, style: &Style, size: Vec2) -> Scene {
+        let screen = Rect::from_min_size(Pos2::ZERO, size);
+        let to_screen = emath::RectTransform::from_to(self.camera.view(screen), screen);
+        let mut scene = Scene::new(size, style.visuals.panel_fill);
+
+        if self.draw_lines {
+            scene
+                .primitives
+                .extend(self.edge_primitives(graph, &to_screen, style));
+        }
+        for node in graph.vertices() {
+            scene.primitives.extend(self.node_primitives(
+                graph,
+                node,
+                &to_screen,
+                style,
+                &style.visuals.widgets.noninteractive,
+            ));
+        }
+
+        scene
     }
 
     pub fn show(
         &mut self,
         ui: &mut Ui,
-        graph: &impl Graph<INPUT_SZ, OUTPUT_SZ>,
-        mut on_select: impl FnMut(usize),
+        graph: &impl Graph<Vertex = V>,
+        mut on_select: impl FnMut(V),
     ) -> Response {
         let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::drag());
 
         let clip_rect = painter.clip_rect();
-        let world_space = Rect::from_center_size(Pos2::ZERO, clip_rect.size() * PIXELS2METERS);
-        let to_screen = emath::RectTransform::from_to(world_space, painter.clip_rect());
-        let from_screen = emath::RectTransform::from_to(painter.clip_rect(), world_space);
+
+        if std::mem::take(&mut self.fit_requested) {
+            if let Some(bounds) = self
+                .entities
+                .values()
+                .map(|entity| Rect::from_center_size(entity.pos, Vec2::ZERO))
+                .reduce(|bounds, rect| bounds.union(rect))
+            {
+                self.camera.fit(bounds, clip_rect.size());
+            } else {
+                self.camera = Camera::default();
+            }
+        }
+
+        // Dragging on empty space pans, dragging a node moves it
+        if response.dragged() {
+            self.camera.center -= response.drag_delta() / self.camera.zoom;
+        }
+        if let Some(pointer) = response.hover_pos() {
+            let scroll = ui.input(|i| i.scroll_delta.y);
+            if scroll != 0.0 {
+                let anchor =
+                    emath::RectTransform::from_to(clip_rect, self.camera.view(clip_rect)) * pointer;
+                self.camera
+                    .zoom_at((scroll * SCROLL_ZOOM_SPEED).exp(), anchor);
+            }
+        }
+
+        let world_space = self.camera.view(clip_rect);
+        let to_screen = emath::RectTransform::from_to(world_space, clip_rect);
+        let from_screen = emath::RectTransform::from_to(clip_rect, world_space);
 
         let dt = ui.input(|i| i.stable_dt);
-        ui.ctx().request_repaint();
-        self.update(dt as f32, graph, world_space);
+        self.step(dt as f32, graph, world_space);
 
         if self.draw_lines {
-            for i in 0..graph.size() {
-                for j in 0..graph.size() {
-                    if graph.connected(Node(i), Node(j)) {
-                        let style = ui.style().noninteractive();
-
-                        painter.line_segment(
-                            [
-                                to_screen * self.entities[i].pos,
-                                to_screen * self.entities[j].pos,
-                            ],
-                            style.fg_stroke,
-                        );
-
-                        if let Some(text) = graph.connection_text(Node(i), Node(j)) {
-                            let font = TextStyle::Small.resolve(ui.style());
-                            let pos = self.entities[i].pos + self.entities[j].pos.to_vec2();
-
-                            painter.text(
-                                to_screen * pos2(pos.x / 2.0, pos.y / 2.0),
-                                Align2::LEFT_CENTER,
-                                " ".to_string() + &text,
-                                font,
-                                style.text_color(),
-                            );
-                        }
-                    }
-                }
+            for primitive in self.edge_primitives(graph, &to_screen, ui.style()) {
+                primitive.paint(&painter);
             }
         }
 
-        for (i, entity) in self.entities.iter_mut().enumerate() {
+        let mut center_on = None;
+        let mut dragged = false;
+
+        for node in graph.vertices() {
+            let Some(entity) = self.entities.get_mut(&node) else {
+                continue;
+            };
             let point_rect = Rect::from_center_size(
                 to_screen * entity.pos,
-                Vec2::splat(NODE_INTERACTION_SIZE * METERS2PIXELS),
+                Vec2::splat(NODE_INTERACTION_SIZE * self.camera.zoom),
             );
-            let point_id = response.id.with(i);
+            let point_id = response.id.with(node);
             let point_response = ui.interact(point_rect, point_id, Sense::click_and_drag());
 
             if point_response.clicked() {
-                on_select(i);
+                on_select(node);
+            }
+            if point_response.secondary_clicked() {
+                center_on = Some(node);
             }
 
             if point_response.dragged() {
                 if let Some(pos) = ui.input(|i| i.pointer.hover_pos()) {
                     entity.pos = from_screen * pos;
+                    dragged = true;
                 }
             }
 
-            let style = ui.style().interact(&point_response);
+            let visuals = ui.style().interact(&point_response);
+            for primitive in self.node_primitives(graph, node, &to_screen, ui.style(), visuals) {
+                primitive.paint(&painter);
+            }
+        }
 
-            let fill = style.bg_fill;
+        if let Some(node) = center_on {
+            self.center_on(node);
+        }
+        if dragged {
+            self.wake();
+        }
 
-            painter.circle_filled(to_screen * entity.pos, NODE_SIZE * METERS2PIXELS, fill);
+        let controls_rect = clip_rect.shrink(ui.spacing().item_spacing.x);
+        ui.allocate_ui_at_rect(controls_rect, |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Fit")
+                    .on_hover_text("Right-click a node to center on it")
+                    .clicked()
+                {
+                    self.fit_all();
+                }
+                let clamp_changed = ui.checkbox(&mut self.clamped, "Clamp").changed();
+                let freeze_changed = ui.checkbox(&mut self.frozen, "Freeze").changed();
+                if clamp_changed || freeze_changed {
+                    self.wake();
+                }
 
-            if let Some(text) = graph.node_text(Node(i)) {
-                let font = TextStyle::Small.resolve(ui.style());
+                if self.export_menu.show(ui) {
+                    let scene = self.scene(graph, ui.style(), clip_rect.size());
+                    self.export_menu.save(&scene);
+                }
+            });
+        });
 
-                painter.text(
-                    to_screen * pos2(entity.pos.x + 1.0, entity.pos.y),
-                    Align2::LEFT_CENTER,
-                    " ".to_string() + &text,
-                    font,
-                    style.text_color(),
-                );
-            }
+        if !self.frozen && !self.settled {
+            ui.ctx().request_repaint();
         }
 
         response
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Path(usize);
+
+    impl Graph for Path {
+        type Vertex = usize;
+
+        fn vertices(&self) -> Vec<usize> {
+            (0..self.0).collect()
+        }
+
+        fn edges(&self) -> Vec<(usize, usize)> {
+            (1..self.0).map(|i| (i - 1, i)).collect()
+        }
+    }
+
+    #[test]
+    fn test_settling() {
+        let mut fd_graph = FDGraph::default();
+        let space = Rect::from_center_size(Pos2::ZERO, Vec2::splat(100.0));
+
+        let settle = |fd_graph: &mut FDGraph<usize>, graph: &Path| {
+            (0..1000)
+                .position(|_| {
+                    fd_graph.step(1.0 / 60.0, graph, space);
+                    fd_graph.settled
+                })
+                .is_some()
+        };
+
+        assert!(settle(&mut fd_graph, &Path(5)));
+        let positions = (0..5)
+            .map(|node| fd_graph.position(node))
+            .collect::<Vec<_>>();
+        fd_graph.step(1.0 / 60.0, &Path(5), space);
+        assert!(fd_graph.settled);
+        assert!((0..5)
+            .zip(positions)
+            .all(|(node, pos)| fd_graph.position(node) == pos));
+
+        // Growing the graph wakes it up
+        fd_graph.step(1.0 / 60.0, &Path(6), space);
+        assert!(!fd_graph.settled);
+        assert!(settle(&mut fd_graph, &Path(6)));
+
+        fd_graph.frozen = true;
+        fd_graph.step(1.0 / 60.0, &Path(8), space);
+        assert!(!fd_graph.settled);
+        assert!(fd_graph.position(7).is_some());
+
+        fd_graph.step(1.0 / 60.0, &Path(3), space);
+        assert_eq!(fd_graph.entities.len(), 3);
+    }
+
+    #[test]
+    fn test_camera() {
+        let mut camera = Camera::default();
+        let screen = Rect::from_min_size(Pos2::ZERO, vec2(200.0, 100.0));
+
+        camera.fit(
+            Rect::from_min_max(pos2(-2.0, -2.0), pos2(6.0, 2.0)),
+            screen.size(),
+        );
+        assert_eq!(camera.center, pos2(2.0, 0.0));
+        assert_eq!(camera.zoom, 100.0 / (4.0 + FIT_MARGIN * 2.0));
+
+        // The anchor stays put
+        let anchor = pos2(4.0, 1.0);
+        let before = emath::RectTransform::from_to(camera.view(screen), screen) * anchor;
+        camera.zoom_at(2.0, anchor);
+        let after = emath::RectTransform::from_to(camera.view(screen), screen) * anchor;
+        assert!(before.distance(after) < 1e-3);
+
+        camera.zoom_at(1e6, anchor);
+        assert_eq!(camera.zoom, MAX_ZOOM);
+    }
+
+    #[test]
+    fn test_edge_paths() {
+        let (path, label_pos) = edge_path(pos2(0.0, 0.0), pos2(10.0, 0.0), false);
+        assert_eq!(path, vec![pos2(0.0, 0.0), pos2(9.0, 0.0)]);
+        assert_eq!(label_pos, pos2(5.0, 0.0));
+
+        // The two directions of a pair bend to opposite sides
+        let (there, there_label) = edge_path(pos2(0.0, 0.0), pos2(10.0, 0.0), true);
+        let (back, back_label) = edge_path(pos2(10.0, 0.0), pos2(0.0, 0.0), true);
+        assert!(there_label.y * back_label.y < 0.0);
+        assert!(there.last().unwrap().distance(pos2(10.0, 0.0)) >= NODE_SIZE);
+        assert!(back.last().unwrap().distance(pos2(0.0, 0.0)) >= NODE_SIZE);
+
+        // Nodes on top of each other don't produce NaN
+        for curved in [false, true] {
+            let (path, label_pos) = edge_path(pos2(3.0, 4.0), pos2(3.0, 4.0), curved);
+            assert_eq!(path, vec![pos2(3.0, 4.0), pos2(3.0, 4.0)]);
+            assert_eq!(label_pos, pos2(3.0, 4.0));
+        }
+
+        // A loop starts and ends on the node, to the left and right of it
+        let (path, label_pos) = loop_path(pos2(0.0, 0.0));
+        let (start, end) = (path[0], *path.last().unwrap());
+        assert!((start.distance(Pos2::ZERO) - NODE_SIZE).abs() < 1e-4);
+        assert!((end.distance(Pos2::ZERO) - NODE_SIZE).abs() < 1e-4);
+        assert!(start.x < 0.0 && end.x > 0.0);
+        assert!(label_pos.y < -NODE_SIZE);
+    }
+}
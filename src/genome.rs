@@ -1,27 +1,28 @@
 use crate::{
+    config::NeatConfig,
     connection::{self, Connection},
     innovation_record::InnovationRecord,
+    lineage::{GenomeId, Mutation},
     node::Node,
 };
 use rand::seq::SliceRandom;
 use rand::Rng;
 use rand_distr::{Distribution, Normal, StandardNormal};
 use std::{
-    collections::{hash_map::DefaultHasher, HashSet},
+    collections::hash_map::DefaultHasher,
+    fmt,
     hash::{Hash, Hasher},
     ops::{Index, IndexMut, RangeInclusive},
+    str::FromStr,
 };
 
 const BIAS: f32 = 1.0;
 
 const INTIAL_WEIGHT_RANGE: RangeInclusive<f32> = -1.0..=1.0;
 
-const MUTATE_WEIGHTS_RATE: f64 = 0.90;
 const MUTATE_PERTURB_WEIGHT_RATE: f64 = 0.90;
 const MUTATE_WEIGHT_POWER: f32 = 0.5;
 const MUTATE_REPLACE_RANGE: RangeInclusive<f32> = -30.0..=30.0;
-const MUTATE_NEW_NODE_RATE: f64 = 0.2;
-const MUTATE_NEW_CONNECTION_RATE: f64 = 0.5;
 
 const CROSSOVER_PICK_FITTEST_CONNECTION_PROB: f64 = 0.9;
 const CROSSOVER_DISABLE_CONNECTION_PROB: f64 = 0.75;
@@ -29,14 +30,26 @@ const CROSSOVER_DISABLE_CONNECTION_PROB: f64 = 0.75;
 const DIST_DISJOINT_FACTOR: f32 = 1.0;
 const DIST_WEIGHT_DIFFERENCE_FACTOR: f32 = 2.0;
 
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Default, Clone)]
 pub struct Genome<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    /// Assigned by [`Lineage`](crate::lineage::Lineage) when the genome
+    /// becomes part of a population.
+    pub id: GenomeId,
     pub hidden_nodes: usize,
     // TODO: Make innovation number the index into a hash set instead of using
     // an vector
     pub connections: Vec<Connection<INPUT_SZ, OUTPUT_SZ>>,
 }
 
+/// Only hashes the network, so [`Genome::identifier`] is the same for copies
+/// of a genome with different ids.
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Hash for Genome<INPUT_SZ, OUTPUT_SZ> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hidden_nodes.hash(state);
+        self.connections.hash(state);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GenomeActivation<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
     pub input: [f32; INPUT_SZ],
@@ -103,6 +116,7 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> IndexMut<Node<INPUT_SZ, OUTP
 impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ> {
     pub fn new() -> Self {
         Self {
+            id: 0,
             hidden_nodes: 0,
             connections: Vec::new(),
         }
@@ -192,21 +206,22 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ>
         //     }
         // }
 
-        // FIXME: Ew
-        let mut hidden_nodes = HashSet::new();
-        for connection in connections.iter() {
-            if connection.in_node.is_hidden() {
-                hidden_nodes.insert(connection.in_node);
-            }
-            if connection.out_node.is_hidden() {
-                hidden_nodes.insert(connection.out_node);
-            }
-        }
-
-        Genome {
-            hidden_nodes: hidden_nodes.len(),
+        let mut offspring = Genome {
+            id: 0,
+            hidden_nodes: 0,
             connections,
-        }
+        };
+        // Counting the distinct hidden nodes isn't enough since the fitter
+        // genome's hidden nodes don't have to be contiguous.
+        offspring.hidden_nodes = offspring.required_hidden_nodes();
+
+        debug_assert_eq!(
+            offspring.validate(),
+            Ok(()),
+            "Crossover produced an invalid genome"
+        );
+
+        offspring
     }
 
     fn mutate_weights(&mut self, rng: &mut impl Rng) {
@@ -226,18 +241,18 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ>
         &mut self,
         rng: &mut impl Rng,
         innovation_db: &mut InnovationRecord<INPUT_SZ, OUTPUT_SZ>,
-    ) {
+    ) -> Option<Mutation> {
         let in_node = Node(rng.gen_range(0..self.nodes()));
         let out_node = Node(rng.gen_range((INPUT_SZ + 1)..self.nodes()));
 
         // TODO: It would be better to not let this happen in the first place
         // rather than returning if it does happen
         if in_node.is_output() {
-            return;
+            return None;
         }
 
         if in_node == out_node {
-            return;
+            return None;
         }
 
         // Find connection with same connecting nodes and ensure it is enabled if
@@ -249,7 +264,7 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ>
             .find(|connection| connection.in_node == in_node && connection.out_node == out_node)
         {
             existing_connection.enabled = true;
-            return;
+            return Some(Mutation::EnableConnection);
         }
 
         // TODO: Disallow cycles
@@ -260,14 +275,16 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ>
             weight: rng.gen(),
             enabled: true,
             innovation_number: innovation_db.get(in_node, out_node),
-        })
+        });
+
+        Some(Mutation::NewConnection)
     }
 
     fn mutate_new_node(
         &mut self,
         rng: &mut impl Rng,
         innovation_db: &mut InnovationRecord<INPUT_SZ, OUTPUT_SZ>,
-    ) {
+    ) -> Option<Mutation> {
         let new_node = Node(self.nodes());
 
         if let Some(mut old_connection) = self.connections.choose_mut(rng) {
@@ -291,7 +308,11 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ>
                 innovation_number: innovation_db.get(new_node, out_node),
             });
             self.hidden_nodes += 1;
+
+            return Some(Mutation::NewNode);
         }
+
+        None
     }
 
     fn mutate_remove_connection(&mut self, rng: &mut impl Rng) {
@@ -303,21 +324,34 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ>
             .remove(rng.gen_range(0..self.connections.len()));
     }
 
+    /// Returns the mutations that were applied.
     pub fn mutate(
         &mut self,
         rng: &mut impl Rng,
         innovation_record: &mut InnovationRecord<INPUT_SZ, OUTPUT_SZ>,
-    ) {
-        if rng.gen_bool(MUTATE_WEIGHTS_RATE) {
+        config: &NeatConfig,
+    ) -> Vec<Mutation> {
+        let mut mutations = Vec::new();
+
+        if rng.gen_bool(config.mutate_weights_rate) {
             self.mutate_weights(rng);
+            mutations.push(Mutation::Weights);
         }
-        if rng.gen_bool(MUTATE_NEW_CONNECTION_RATE) {
-            self.mutate_new_connection(rng, innovation_record);
+        if rng.gen_bool(config.mutate_new_connection_rate) {
+            mutations.extend(self.mutate_new_connection(rng, innovation_record));
         }
-        if rng.gen_bool(MUTATE_NEW_NODE_RATE) {
-            self.mutate_new_node(rng, innovation_record);
+        if rng.gen_bool(config.mutate_new_node_rate) {
+            mutations.extend(self.mutate_new_node(rng, innovation_record));
         }
         // TODO: Remove nodes, delete connections
+
+        debug_assert_eq!(
+            self.validate(),
+            Ok(()),
+            "Mutation produced an invalid genome"
+        );
+
+        mutations
     }
 
     fn activation_function(x: f32) -> f32 {
@@ -419,13 +453,132 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ>
     }
 }
 
+/// A single line of text: the number of hidden nodes followed by one
+/// `in>out:weight:enabled:innovation` entry per connection, e.g.
+/// `1 0>3:-0.84:0:0 0>4:1:1:10`.
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> fmt::Display for Genome<INPUT_SZ, OUTPUT_SZ> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.hidden_nodes)?;
+
+        for connection in &self.connections {
+            write!(
+                f,
+                " {}>{}:{}:{}:{}",
+                connection.in_node.0,
+                connection.out_node.0,
+                connection.weight,
+                connection.enabled as u8,
+                connection.innovation_number
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> FromStr for Genome<INPUT_SZ, OUTPUT_SZ> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+
+        let hidden_nodes = parts
+            .next()
+            .ok_or("Missing hidden node count")?
+            .parse()
+            .map_err(|_| "Invalid hidden node count")?;
+
+        let connections = parts
+            .map(|part| {
+                let invalid = || format!("Invalid connection `{}`", part);
+
+                let (nodes, rest) = part.split_once(':').ok_or_else(invalid)?;
+                let (in_node, out_node) = nodes.split_once('>').ok_or_else(invalid)?;
+                let mut fields = rest.split(':');
+                let mut field = || fields.next().ok_or_else(invalid);
+
+                Ok(Connection {
+                    in_node: Node(in_node.parse().map_err(|_| invalid())?),
+                    out_node: Node(out_node.parse().map_err(|_| invalid())?),
+                    weight: field()?.parse().map_err(|_| invalid())?,
+                    enabled: field()? == "1",
+                    innovation_number: field()?.parse().map_err(|_| invalid())?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        let genome = Genome {
+            id: 0,
+            hidden_nodes,
+            connections,
+        };
+
+        genome.validate().map_err(|errors| {
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        })?;
+
+        Ok(genome)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_identifier_ignores_id() {
+        let mut genome = Genome::<2, 1>::new();
+        genome.connect(Node(1), Node(3), 0.5, &mut InnovationRecord::default());
+        let mut copy = genome.clone();
+        copy.id = genome.id + 1;
+
+        assert_eq!(genome.identifier(), copy.identifier());
+
+        copy.hidden_nodes += 1;
+        assert_ne!(genome.identifier(), copy.identifier());
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let mut genome = Genome::<2, 1>::new();
+        genome.hidden_nodes = 1;
+        genome.connections.push(Connection {
+            in_node: Node(0),
+            out_node: Node(4),
+            weight: -0.84480023,
+            enabled: false,
+            innovation_number: 10,
+        });
+        genome.connections.push(Connection {
+            in_node: Node(4),
+            out_node: Node(3),
+            weight: 3.6848402,
+            enabled: true,
+            innovation_number: 11,
+        });
+
+        let text = genome.to_string();
+        assert_eq!(text, "1 0>4:-0.84480023:0:10 4>3:3.6848402:1:11");
+
+        let parsed = text.parse::<Genome<2, 1>>().unwrap();
+        assert_eq!(parsed.identifier(), genome.identifier());
+
+        assert!("".parse::<Genome<2, 1>>().is_err());
+        assert!("0 1>3:x:1:0".parse::<Genome<2, 1>>().is_err());
+        assert_eq!(
+            "0 4>3:1:1:0".parse::<Genome<2, 1>>().unwrap_err(),
+            "Node 4 is out of range for a genome with 0 hidden nodes"
+        );
+    }
+
     #[test]
     fn test_distance() {
         dbg!(Genome::<2, 1> {
+            id: 0,
             hidden_nodes: 1,
             connections: vec![
                 Connection {
@@ -473,6 +626,7 @@ mod tests {
             ],
         }
         .distance(&Genome {
+            id: 0,
             hidden_nodes: 1,
             connections: vec![
                 Connection {
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use crate::{connection::Connection, genome::Genome};
+
+/// Which of the two compared genomes a gene belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    This,
+    Other,
+}
+
+/// A gene both genomes share, i.e. with the same innovation number.
+#[derive(Debug, Clone)]
+pub struct MatchingGene<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    pub this: Connection<INPUT_SZ, OUTPUT_SZ>,
+    pub other: Connection<INPUT_SZ, OUTPUT_SZ>,
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> MatchingGene<INPUT_SZ, OUTPUT_SZ> {
+    pub fn weight_delta(&self) -> f32 {
+        self.other.weight - self.this.weight
+    }
+
+    pub fn enabled_changed(&self) -> bool {
+        self.this.enabled != self.other.enabled
+    }
+}
+
+/// Genes of two genomes aligned by innovation number, as in the NEAT paper.
+/// Genes only one genome has are disjoint if they fall within the other
+/// genome's range of innovation numbers and excess otherwise.
+#[derive(Debug, Clone)]
+pub struct GenomeDiff<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    pub matching: Vec<MatchingGene<INPUT_SZ, OUTPUT_SZ>>,
+    pub disjoint: Vec<(Side, Connection<INPUT_SZ, OUTPUT_SZ>)>,
+    pub excess: Vec<(Side, Connection<INPUT_SZ, OUTPUT_SZ>)>,
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> GenomeDiff<INPUT_SZ, OUTPUT_SZ> {
+    /// `(innovation number, weight delta)` of every matching gene.
+    pub fn weight_deltas(&self) -> impl Iterator<Item = (usize, f32)> + '_ {
+        self.matching
+            .iter()
+            .map(|gene| (gene.this.innovation_number, gene.weight_delta()))
+    }
+
+    /// Matching genes that are enabled in one genome but not the other.
+    pub fn enable_changes(&self) -> impl Iterator<Item = &MatchingGene<INPUT_SZ, OUTPUT_SZ>> {
+        self.matching.iter().filter(|gene| gene.enabled_changed())
+    }
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ> {
+    pub fn diff(&self, other: &Genome<INPUT_SZ, OUTPUT_SZ>) -> GenomeDiff<INPUT_SZ, OUTPUT_SZ> {
+        let this_genes = self
+            .connections
+            .iter()
+            .map(|connection| (connection.innovation_number, connection))
+            .collect::<HashMap<_, _>>();
+        let other_genes = other
+            .connections
+            .iter()
+            .map(|connection| (connection.innovation_number, connection))
+            .collect::<HashMap<_, _>>();
+
+        let this_max = this_genes.keys().max().copied();
+        let other_max = other_genes.keys().max().copied();
+
+        let mut diff = GenomeDiff {
+            matching: Vec::new(),
+            disjoint: Vec::new(),
+            excess: Vec::new(),
+        };
+
+        let mut unmatched = |side: Side, connection: &Connection<INPUT_SZ, OUTPUT_SZ>| {
+            let other_max = match side {
+                Side::This => other_max,
+                Side::Other => this_max,
+            };
+
+            if other_max.is_none_or(|max| connection.innovation_number > max) {
+                diff.excess.push((side, connection.clone()));
+            } else {
+                diff.disjoint.push((side, connection.clone()));
+            }
+        };
+
+        for connection in &self.connections {
+            if !other_genes.contains_key(&connection.innovation_number) {
+                unmatched(Side::This, connection);
+            }
+        }
+        for connection in &other.connections {
+            if !this_genes.contains_key(&connection.innovation_number) {
+                unmatched(Side::Other, connection);
+            }
+        }
+
+        for connection in &self.connections {
+            if let Some(other_connection) = other_genes.get(&connection.innovation_number) {
+                diff.matching.push(MatchingGene {
+                    this: connection.clone(),
+                    other: (*other_connection).clone(),
+                });
+            }
+        }
+
+        diff.matching
+            .sort_by_key(|gene| gene.this.innovation_number);
+        diff.disjoint
+            .sort_by_key(|(_, connection)| connection.innovation_number);
+        diff.excess
+            .sort_by_key(|(_, connection)| connection.innovation_number);
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::Node;
+
+    fn connection(in_node: usize, out_node: usize, innovation_number: usize) -> Connection<2, 1> {
+        Connection {
+            in_node: Node(in_node),
+            out_node: Node(out_node),
+            weight: 1.0,
+            enabled: true,
+            innovation_number,
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut this = Genome::<2, 1>::new();
+        this.connections = vec![
+            connection(0, 3, 0),
+            connection(1, 3, 1),
+            connection(0, 4, 4),
+        ];
+        let mut other = Genome::<2, 1>::new();
+        other.connections = vec![
+            connection(0, 3, 0),
+            connection(1, 3, 1),
+            connection(2, 3, 2),
+            connection(4, 3, 7),
+        ];
+        other.connections[0].weight = 0.25;
+        other.connections[1].enabled = false;
+
+        let diff = this.diff(&other);
+
+        assert_eq!(diff.matching.len(), 2);
+        assert_eq!(
+            diff.weight_deltas().collect::<Vec<_>>(),
+            vec![(0, -0.75), (1, 0.0)]
+        );
+        assert_eq!(
+            diff.enable_changes()
+                .map(|gene| gene.this.innovation_number)
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(
+            diff.disjoint
+                .iter()
+                .map(|(side, connection)| (*side, connection.innovation_number))
+                .collect::<Vec<_>>(),
+            vec![(Side::Other, 2), (Side::This, 4)]
+        );
+        assert_eq!(
+            diff.excess
+                .iter()
+                .map(|(side, connection)| (*side, connection.innovation_number))
+                .collect::<Vec<_>>(),
+            vec![(Side::Other, 7)]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use eframe::{
+    egui::{self, RichText},
+    epaint::Color32,
+};
+use egui_extras::{Column, TableBuilder};
+
+use crate::{
+    connection::Connection,
+    genome::Genome,
+    genome_diff::{GenomeDiff, Side},
+};
+
+const DISJOINT_COLOR: Color32 = Color32::LIGHT_BLUE;
+const EXCESS_COLOR: Color32 = Color32::LIGHT_RED;
+
+enum Row<'a, const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    Matching(
+        &'a Connection<INPUT_SZ, OUTPUT_SZ>,
+        &'a Connection<INPUT_SZ, OUTPUT_SZ>,
+    ),
+    Unmatched(Side, &'a Connection<INPUT_SZ, OUTPUT_SZ>, Color32),
+}
+
+/// Shows two genomes side by side with their genes aligned by innovation
+/// number. Disjoint and excess genes, changed weights and changed enable
+/// states are highlighted.
+pub struct GenomeDiffVisualizer<'a, const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    pub this: &'a Genome<INPUT_SZ, OUTPUT_SZ>,
+    pub other: &'a Genome<INPUT_SZ, OUTPUT_SZ>,
+}
+
+fn gene_text<const INPUT_SZ: usize, const OUTPUT_SZ: usize>(
+    connection: &Connection<INPUT_SZ, OUTPUT_SZ>,
+) -> String {
+    format!(
+        "{} → {}: {:.2}{}",
+        connection.in_node.0,
+        connection.out_node.0,
+        connection.weight,
+        if connection.enabled {
+            ""
+        } else {
+            " (disabled)"
+        }
+    )
+}
+
+impl<'a, const INPUT_SZ: usize, const OUTPUT_SZ: usize> egui::Widget
+    for GenomeDiffVisualizer<'a, INPUT_SZ, OUTPUT_SZ>
+{
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let diff: GenomeDiff<INPUT_SZ, OUTPUT_SZ> = self.this.diff(self.other);
+
+        let mut rows = diff
+            .matching
+            .iter()
+            .map(|gene| Row::Matching(&gene.this, &gene.other))
+            .chain(
+                diff.disjoint
+                    .iter()
+                    .map(|(side, connection)| Row::Unmatched(*side, connection, DISJOINT_COLOR)),
+            )
+            .chain(
+                diff.excess
+                    .iter()
+                    .map(|(side, connection)| Row::Unmatched(*side, connection, EXCESS_COLOR)),
+            )
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|row| match row {
+            Row::Matching(connection, _) | Row::Unmatched(_, connection, _) => {
+                connection.innovation_number
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{} matching", diff.matching.len()));
+            ui.label(
+                RichText::new(format!("{} disjoint", diff.disjoint.len())).color(DISJOINT_COLOR),
+            );
+            ui.label(RichText::new(format!("{} excess", diff.excess.len())).color(EXCESS_COLOR));
+            ui.label(format!("Distance: {:.2}", self.this.distance(self.other)));
+        });
+        ui.separator();
+
+        let interact_height = ui.spacing().interact_size.y;
+        let warn_color = ui.visuals().warn_fg_color;
+
+        ui.push_id("genome_diff", |ui| {
+            TableBuilder::new(ui)
+                .striped(true)
+                .column(Column::auto())
+                .column(Column::initial(140.0))
+                .column(Column::initial(140.0))
+                .column(Column::remainder().at_least(40.0))
+                .header(interact_height, |mut header| {
+                    header.col(|ui| {
+                        ui.label("Innovation");
+                    });
+                    header.col(|ui| {
+                        ui.label(self.this.identifier());
+                    });
+                    header.col(|ui| {
+                        ui.label(self.other.identifier());
+                    });
+                    header.col(|ui| {
+                        ui.label("Δ weight");
+                    });
+                })
+                .body(|mut body| {
+                    for row in rows {
+                        body.row(interact_height, |mut table_row| match row {
+                            Row::Matching(this, other) => {
+                                let color = if this.enabled != other.enabled {
+                                    Some(warn_color)
+                                } else {
+                                    None
+                                };
+                                let text = |connection| {
+                                    let text = RichText::new(gene_text(connection));
+                                    match color {
+                                        Some(color) => text.color(color),
+                                        None => text,
+                                    }
+                                };
+
+                                table_row.col(|ui| {
+                                    ui.label(this.innovation_number.to_string());
+                                });
+                                table_row.col(|ui| {
+                                    ui.label(text(this));
+                                });
+                                table_row.col(|ui| {
+                                    ui.label(text(other));
+                                });
+                                table_row.col(|ui| {
+                                    let delta = other.weight - this.weight;
+                                    if delta != 0.0 {
+                                        ui.label(format!("{:+.2}", delta));
+                                    }
+                                });
+                            }
+                            Row::Unmatched(side, connection, color) => {
+                                let text = RichText::new(gene_text(connection)).color(color);
+
+                                table_row.col(|ui| {
+                                    ui.label(
+                                        RichText::new(connection.innovation_number.to_string())
+                                            .color(color),
+                                    );
+                                });
+                                table_row.col(|ui| {
+                                    if side == Side::This {
+                                        ui.label(text.clone());
+                                    }
+                                });
+                                table_row.col(|ui| {
+                                    if side == Side::Other {
+                                        ui.label(text);
+                                    }
+                                });
+                                table_row.col(|_| {});
+                            }
+                        });
+                    }
+                });
+        })
+        .response
+    }
+}
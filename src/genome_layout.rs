@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+
+use crate::{genome::Genome, node::Node};
+
+const BARYCENTER_SWEEPS: usize = 4;
+
+/// Finds the edges that close a cycle when walking the graph depth first
+/// from the bias and inputs, so the rest of the edges form a DAG.
+fn back_edges(nodes: usize, edges: &[(usize, usize)]) -> HashSet<(usize, usize)> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        Unvisited,
+        InProgress,
+        Done,
+    }
+
+    let mut visits = vec![Visit::Unvisited; nodes];
+    let mut back_edges = HashSet::new();
+
+    for start in 0..nodes {
+        if visits[start] != Visit::Unvisited {
+            continue;
+        }
+
+        // The node and how many of its outgoing edges were looked at
+        let mut stack = vec![(start, 0)];
+        visits[start] = Visit::InProgress;
+
+        while let Some((node, next_edge)) = stack.last_mut() {
+            let node = *node;
+            let outgoing = edges.iter().filter(|(from, _)| *from == node);
+
+            if let Some(&(_, to)) = outgoing.clone().nth(*next_edge) {
+                *next_edge += 1;
+
+                match visits[to] {
+                    Visit::Unvisited => {
+                        visits[to] = Visit::InProgress;
+                        stack.push((to, 0));
+                    }
+                    Visit::InProgress => {
+                        back_edges.insert((node, to));
+                    }
+                    Visit::Done => {}
+                }
+            } else {
+                visits[node] = Visit::Done;
+                stack.pop();
+            }
+        }
+    }
+
+    back_edges
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ> {
+    /// The nodes arranged in layers for drawing from left to right. The bias
+    /// and inputs make up the first layer and the outputs the last. Hidden
+    /// nodes are placed by their longest path from the inputs, ignoring
+    /// recurrent connections, and ordered within their layer to reduce edge
+    /// crossings using the barycenter heuristic.
+    pub fn layers(&self) -> Vec<Vec<Node<INPUT_SZ, OUTPUT_SZ>>> {
+        let nodes = self.nodes();
+        let edges = self
+            .connections
+            .iter()
+            .filter(|connection| connection.enabled)
+            .map(|connection| (connection.in_node.0, connection.out_node.0))
+            .collect::<Vec<_>>();
+        let back_edges = back_edges(nodes, &edges);
+        let forward_edges = edges
+            .iter()
+            .copied()
+            .filter(|edge| !back_edges.contains(edge))
+            .collect::<Vec<_>>();
+
+        // Longest path by relaxing every edge until nothing changes, which
+        // takes at most as many rounds as there are nodes in a DAG.
+        let mut layer = vec![0; nodes];
+        for node in (0..nodes).map(Node::<INPUT_SZ, OUTPUT_SZ>) {
+            if node.is_hidden() {
+                layer[node.0] = 1;
+            }
+        }
+        for _ in 0..nodes {
+            let mut changed = false;
+
+            for &(from, to) in &forward_edges {
+                if !Node::<INPUT_SZ, OUTPUT_SZ>(to).is_output() && layer[to] < layer[from] + 1 {
+                    layer[to] = layer[from] + 1;
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let output_layer = (0..nodes)
+            .filter(|&node| Node::<INPUT_SZ, OUTPUT_SZ>(node).is_hidden())
+            .map(|node| layer[node] + 1)
+            .max()
+            .unwrap_or(1);
+
+        let mut layers = vec![Vec::new(); output_layer + 1];
+        for node in (0..nodes).map(Node) {
+            if node.is_output() {
+                layers[output_layer].push(node);
+            } else {
+                layers[layer[node.0]].push(node);
+            }
+        }
+
+        for _ in 0..BARYCENTER_SWEEPS {
+            for index in 1..layers.len().saturating_sub(1) {
+                order_by_barycenter(&mut layers, index, &forward_edges, true);
+            }
+            for index in (1..layers.len().saturating_sub(1)).rev() {
+                order_by_barycenter(&mut layers, index, &forward_edges, false);
+            }
+        }
+
+        layers
+    }
+}
+
+/// Sorts a layer by the average relative position of each node's neighbours
+/// in the layers before it, or after it if `!from_before`.
+fn order_by_barycenter<const INPUT_SZ: usize, const OUTPUT_SZ: usize>(
+    layers: &mut [Vec<Node<INPUT_SZ, OUTPUT_SZ>>],
+    index: usize,
+    edges: &[(usize, usize)],
+    from_before: bool,
+) {
+    let position = |node: usize| {
+        layers.iter().enumerate().find_map(|(layer_index, layer)| {
+            let rank = layer.iter().position(|other| other.0 == node)?;
+            Some((layer_index, (rank as f32 + 0.5) / layer.len() as f32))
+        })
+    };
+
+    let mut barycenters = layers[index]
+        .iter()
+        .enumerate()
+        .map(|(rank, node)| {
+            let neighbours = edges
+                .iter()
+                .filter_map(|&(from, to)| {
+                    let neighbour = if to == node.0 {
+                        from
+                    } else if from == node.0 {
+                        to
+                    } else {
+                        return None;
+                    };
+                    let (layer_index, position) = position(neighbour)?;
+
+                    if (layer_index < index) == from_before && layer_index != index {
+                        Some(position)
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let barycenter = if neighbours.is_empty() {
+                (rank as f32 + 0.5) / layers[index].len() as f32
+            } else {
+                neighbours.iter().sum::<f32>() / neighbours.len() as f32
+            };
+
+            (barycenter, *node)
+        })
+        .collect::<Vec<_>>();
+
+    barycenters.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    layers[index] = barycenters.into_iter().map(|(_, node)| node).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::Connection;
+
+    fn connection(in_node: usize, out_node: usize, innovation_number: usize) -> Connection<2, 1> {
+        Connection {
+            in_node: Node(in_node),
+            out_node: Node(out_node),
+            weight: 1.0,
+            enabled: true,
+            innovation_number,
+        }
+    }
+
+    fn layer_ids(genome: &Genome<2, 1>) -> Vec<Vec<usize>> {
+        genome
+            .layers()
+            .into_iter()
+            .map(|layer| layer.into_iter().map(|node| node.0).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut genome = Genome::<2, 1>::new();
+        genome.hidden_nodes = 3;
+        genome.connections = vec![
+            connection(1, 4, 0),
+            connection(4, 5, 1),
+            connection(5, 3, 2),
+            // Recurrent
+            connection(5, 4, 3),
+            connection(2, 6, 4),
+            connection(6, 3, 5),
+        ];
+
+        // The recurrent connection doesn't push 4 after 5, and 6 goes below
+        // 4 since it comes from the second input
+        assert_eq!(
+            layer_ids(&genome),
+            vec![vec![0, 1, 2], vec![4, 6], vec![5], vec![3]]
+        );
+
+        genome.connections.swap(0, 4);
+        assert_eq!(
+            layer_ids(&genome),
+            vec![vec![0, 1, 2], vec![4, 6], vec![5], vec![3]]
+        );
+
+        genome.connections.clear();
+        assert_eq!(
+            layer_ids(&genome),
+            vec![vec![0, 1, 2], vec![4, 5, 6], vec![3]]
+        );
+    }
+}
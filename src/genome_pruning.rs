@@ -0,0 +1,180 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{connection::Connection, genome::Genome, node::Node};
+
+fn reachable<const INPUT_SZ: usize, const OUTPUT_SZ: usize>(
+    start: impl Iterator<Item = Node<INPUT_SZ, OUTPUT_SZ>>,
+    edges: &[(Node<INPUT_SZ, OUTPUT_SZ>, Node<INPUT_SZ, OUTPUT_SZ>)],
+) -> HashSet<Node<INPUT_SZ, OUTPUT_SZ>> {
+    let mut reached = start.collect::<HashSet<_>>();
+    let mut queue = reached.iter().copied().collect::<VecDeque<_>>();
+
+    while let Some(node) = queue.pop_front() {
+        for &(from, to) in edges {
+            if from == node && reached.insert(to) {
+                queue.push_back(to);
+            }
+        }
+    }
+
+    reached
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ> {
+    /// A copy without disabled connections and without hidden nodes that
+    /// either can't reach an output or can't be reached from an input or the
+    /// bias. Remaining hidden nodes are renumbered to be contiguous.
+    ///
+    /// Hidden nodes that can't be reached still feed a constant into the
+    /// nodes they connect to, so that constant is folded into the bias
+    /// connection of those nodes. The phenotype is therefore equivalent once
+    /// the removed nodes have settled, which for acyclic structure takes as
+    /// many activation steps as it is deep.
+    ///
+    /// The renumbered hidden nodes no longer line up with the innovation
+    /// record, so the result is meant for inspecting and running a genome,
+    /// not for putting it back into a population.
+    pub fn pruned(&self) -> Genome<INPUT_SZ, OUTPUT_SZ> {
+        let enabled = self
+            .connections
+            .iter()
+            .filter(|connection| connection.enabled)
+            .collect::<Vec<_>>();
+        let edges = enabled
+            .iter()
+            .map(|connection| (connection.in_node, connection.out_node))
+            .collect::<Vec<_>>();
+        let reversed_edges = edges
+            .iter()
+            .map(|&(from, to)| (to, from))
+            .collect::<Vec<_>>();
+
+        let from_inputs = reachable((0..=INPUT_SZ).map(Node), &edges);
+        let to_outputs = reachable((0..OUTPUT_SZ).map(Node::from_output_index), &reversed_edges);
+
+        let hidden = (0..self.hidden_nodes).map(Node::from_hidden_index);
+        let renumbered = hidden
+            .clone()
+            .filter(|node| from_inputs.contains(node) && to_outputs.contains(node))
+            .enumerate()
+            .map(|(index, node)| (node, Node::from_hidden_index(index)))
+            .collect::<HashMap<_, _>>();
+        let renumber = |node: Node<INPUT_SZ, OUTPUT_SZ>| {
+            if node.is_hidden() {
+                renumbered.get(&node).copied()
+            } else {
+                Some(node)
+            }
+        };
+
+        let settled = self.activate([0.0; INPUT_SZ]);
+        let constant = hidden
+            .filter(|node| !from_inputs.contains(node) && to_outputs.contains(node))
+            .collect::<HashSet<_>>();
+
+        let mut connections: Vec<Connection<INPUT_SZ, OUTPUT_SZ>> = Vec::new();
+        let mut folded = Vec::new();
+
+        for connection in enabled {
+            let Some(out_node) = renumber(connection.out_node) else {
+                continue;
+            };
+
+            if let Some(in_node) = renumber(connection.in_node) {
+                connections.push(Connection {
+                    in_node,
+                    out_node,
+                    ..connection.clone()
+                });
+            } else if constant.contains(&connection.in_node) {
+                folded.push((
+                    out_node,
+                    settled[connection.in_node] * connection.weight,
+                    connection.innovation_number,
+                ));
+            }
+        }
+
+        for (out_node, weight, innovation_number) in folded {
+            if let Some(bias_connection) = connections
+                .iter_mut()
+                .find(|connection| connection.in_node.is_bias() && connection.out_node == out_node)
+            {
+                bias_connection.weight += weight;
+            } else {
+                connections.push(Connection {
+                    in_node: Node::BIAS,
+                    out_node,
+                    weight,
+                    enabled: true,
+                    innovation_number,
+                });
+            }
+        }
+
+        Genome {
+            id: self.id,
+            hidden_nodes: renumbered.len(),
+            connections,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(
+        in_node: usize,
+        out_node: usize,
+        weight: f32,
+        innovation_number: usize,
+    ) -> Connection<2, 1> {
+        Connection {
+            in_node: Node(in_node),
+            out_node: Node(out_node),
+            weight,
+            enabled: true,
+            innovation_number,
+        }
+    }
+
+    #[test]
+    fn test_pruned() {
+        let mut genome = Genome::<2, 1>::new();
+        genome.hidden_nodes = 4;
+        genome.connections = vec![
+            connection(1, 3, 0.5, 0),
+            connection(2, 3, -1.5, 1),
+            // Disabled
+            connection(1, 4, 2.0, 2),
+            // Dead end: 5 never reaches the output
+            connection(2, 5, 1.0, 3),
+            // Unreachable: 6 has no inputs and feeds a constant into 7
+            connection(6, 7, 3.0, 4),
+            connection(2, 7, 1.0, 5),
+            connection(7, 3, -2.0, 6),
+        ];
+        genome.connections[2].enabled = false;
+
+        let pruned = genome.pruned();
+
+        assert_eq!(pruned.hidden_nodes, 1);
+        assert_eq!(pruned.validate(), Ok(()));
+        assert_eq!(
+            pruned
+                .connections
+                .iter()
+                .map(|connection| (connection.in_node.0, connection.out_node.0))
+                .collect::<Vec<_>>(),
+            vec![(1, 3), (2, 3), (2, 4), (4, 3), (0, 4)]
+        );
+        assert_eq!(pruned.connections[4].weight, 1.5);
+
+        for input in [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]] {
+            let expected = genome.activate(input).output[0];
+            let actual = pruned.activate(input).output[0];
+            assert!((expected - actual).abs() < 1e-5);
+        }
+    }
+}
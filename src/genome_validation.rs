@@ -0,0 +1,180 @@
+use std::{collections::HashSet, fmt};
+
+use crate::{genome::Genome, node::Node};
+
+/// A broken invariant of a [`Genome`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenomeError {
+    /// A connection refers to a node the genome doesn't have, usually a
+    /// hidden node past `hidden_nodes`.
+    NodeOutOfRange {
+        node: usize,
+        hidden_nodes: usize,
+    },
+    /// Output nodes can only be the target of a connection.
+    ConnectionFromOutput {
+        innovation_number: usize,
+    },
+    /// The bias and input nodes can only be the source of a connection.
+    ConnectionIntoInput {
+        innovation_number: usize,
+    },
+    DuplicateConnection {
+        in_node: usize,
+        out_node: usize,
+    },
+    DuplicateInnovation {
+        innovation_number: usize,
+    },
+}
+
+impl fmt::Display for GenomeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenomeError::NodeOutOfRange { node, hidden_nodes } => write!(
+                f,
+                "Node {} is out of range for a genome with {} hidden nodes",
+                node, hidden_nodes
+            ),
+            GenomeError::ConnectionFromOutput { innovation_number } => write!(
+                f,
+                "Connection {} starts at an output node",
+                innovation_number
+            ),
+            GenomeError::ConnectionIntoInput { innovation_number } => write!(
+                f,
+                "Connection {} ends at an input or bias node",
+                innovation_number
+            ),
+            GenomeError::DuplicateConnection { in_node, out_node } => write!(
+                f,
+                "Nodes {} and {} are connected more than once",
+                in_node, out_node
+            ),
+            GenomeError::DuplicateInnovation { innovation_number } => write!(
+                f,
+                "Innovation number {} is used more than once",
+                innovation_number
+            ),
+        }
+    }
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Genome<INPUT_SZ, OUTPUT_SZ> {
+    pub fn validate(&self) -> Result<(), Vec<GenomeError>> {
+        let mut errors = Vec::new();
+        let mut pairs = HashSet::new();
+        let mut innovation_numbers = HashSet::new();
+
+        for connection in &self.connections {
+            for node in [connection.in_node, connection.out_node] {
+                if node.0 >= self.nodes() {
+                    errors.push(GenomeError::NodeOutOfRange {
+                        node: node.0,
+                        hidden_nodes: self.hidden_nodes,
+                    });
+                }
+            }
+
+            if connection.in_node.is_output() {
+                errors.push(GenomeError::ConnectionFromOutput {
+                    innovation_number: connection.innovation_number,
+                });
+            }
+
+            if connection.out_node.is_bias() || connection.out_node.is_input() {
+                errors.push(GenomeError::ConnectionIntoInput {
+                    innovation_number: connection.innovation_number,
+                });
+            }
+
+            if !pairs.insert((connection.in_node, connection.out_node)) {
+                errors.push(GenomeError::DuplicateConnection {
+                    in_node: connection.in_node.0,
+                    out_node: connection.out_node.0,
+                });
+            }
+
+            if !innovation_numbers.insert(connection.innovation_number) {
+                errors.push(GenomeError::DuplicateInnovation {
+                    innovation_number: connection.innovation_number,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The smallest `hidden_nodes` that covers every hidden node referenced
+    /// by `connections`.
+    pub fn required_hidden_nodes(&self) -> usize {
+        self.connections
+            .iter()
+            .flat_map(|connection| [connection.in_node, connection.out_node])
+            .filter(Node::is_hidden)
+            .map(|node| node.0 - INPUT_SZ - OUTPUT_SZ)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::Connection;
+
+    fn connection(in_node: usize, out_node: usize, innovation_number: usize) -> Connection<2, 1> {
+        Connection {
+            in_node: Node(in_node),
+            out_node: Node(out_node),
+            weight: 1.0,
+            enabled: true,
+            innovation_number,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut genome = Genome::<2, 1>::new();
+        genome.hidden_nodes = 1;
+        genome.connections = vec![
+            connection(0, 3, 0),
+            connection(0, 4, 10),
+            connection(4, 3, 4),
+        ];
+        assert_eq!(genome.validate(), Ok(()));
+
+        // Like the `test_distance` fixture, which reuses (4, 3)
+        genome.connections.push(connection(4, 3, 11));
+        genome.connections.push(connection(5, 3, 4));
+        genome.connections.push(connection(3, 1, 12));
+
+        assert_eq!(
+            genome.validate(),
+            Err(vec![
+                GenomeError::DuplicateConnection {
+                    in_node: 4,
+                    out_node: 3
+                },
+                GenomeError::NodeOutOfRange {
+                    node: 5,
+                    hidden_nodes: 1
+                },
+                GenomeError::DuplicateInnovation {
+                    innovation_number: 4
+                },
+                GenomeError::ConnectionFromOutput {
+                    innovation_number: 12
+                },
+                GenomeError::ConnectionIntoInput {
+                    innovation_number: 12
+                },
+            ])
+        );
+        assert_eq!(genome.required_hidden_nodes(), 2);
+    }
+}
@@ -1,16 +1,33 @@
 use crate::client::ClientId;
-use crate::force_directed_graph::{FDGraph, Graph, NodeEntity};
+use crate::decision_boundary::DecisionBoundary;
+use crate::environment::TestCases;
+use crate::evaluation::EvaluationResult;
+use crate::force_directed_graph::{EdgeStyle, FDGraph, Graph, NodeEntity, NodeStyle};
 use crate::genome::{Genome, GenomeActivation};
+use crate::lineage::Lineage;
 use crate::node::Node;
 use eframe::egui::{CentralPanel, TopBottomPanel};
-use eframe::{egui, epaint::pos2};
+use eframe::{
+    egui,
+    epaint::{pos2, Color32},
+};
+use egui_extras::{Column, TableBuilder};
 
 const INPUT_OUTPUT_DIST: f32 = 35.0;
 const ADJACENT_NODE_DIST: f32 = 10.0;
+const POSITIVE_WEIGHT_COLOR: Color32 = Color32::from_rgb(90, 170, 255);
+const NEGATIVE_WEIGHT_COLOR: Color32 = Color32::from_rgb(255, 100, 90);
+const DISABLED_CONNECTION_COLOR: Color32 = Color32::GRAY;
+/// Connections with at least this absolute weight are drawn the widest.
+const MAX_WIDTH_WEIGHT: f32 = 3.0;
+const MIN_CONNECTION_WIDTH: f32 = 0.5;
+const MAX_CONNECTION_WIDTH: f32 = 4.0;
 
 pub struct GenomeGraph<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
     pub genome: Genome<INPUT_SZ, OUTPUT_SZ>,
     pub activation: GenomeActivation<INPUT_SZ, OUTPUT_SZ>,
+    /// Whether disabled connections are drawn as dashed lines.
+    pub show_disabled: bool,
 }
 
 impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> GenomeGraph<INPUT_SZ, OUTPUT_SZ> {
@@ -24,9 +41,28 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> GenomeGraph<INPUT_SZ, OUTPUT
 
 pub struct GenomeVisualizer<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
     client_id: ClientId,
+    pub window_id: egui::Id,
     test_inputs: [f32; INPUT_SZ],
+    pub genome: Genome<INPUT_SZ, OUTPUT_SZ>,
+    /// How `genome` did when the window was opened.
+    result: Option<EvaluationResult>,
+    /// Whether `genome_graph` shows [`Genome::pruned`] instead of `genome`.
+    pruned: bool,
+    /// Whether nodes are pinned to [`Genome::layers`] instead of floating
+    /// around under the force-directed physics.
+    layered: bool,
     pub genome_graph: GenomeGraph<INPUT_SZ, OUTPUT_SZ>,
-    fd_graph: FDGraph<INPUT_SZ, OUTPUT_SZ>,
+    fd_graph: FDGraph<Node<INPUT_SZ, OUTPUT_SZ>>,
+    /// The descendants we walked up from, most recent last.
+    trail: Vec<Genome<INPUT_SZ, OUTPUT_SZ>>,
+    /// Whether the first two inputs are swept in a heatmap.
+    show_boundary: bool,
+    decision_boundary: DecisionBoundary,
+    /// Listed in a table and drawn over the heatmap, see
+    /// [`Environment::test_cases`].
+    ///
+    /// [`Environment::test_cases`]: crate::environment::Environment::test_cases
+    test_cases: TestCases<INPUT_SZ, OUTPUT_SZ>,
 }
 
 impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> GenomeVisualizer<INPUT_SZ, OUTPUT_SZ> {
@@ -62,34 +98,315 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> GenomeVisualizer<INPUT_SZ, O
         })
     }
 
-    pub fn new(genome: Genome<INPUT_SZ, OUTPUT_SZ>, client_id: ClientId) -> Self {
+    pub fn new(
+        genome: Genome<INPUT_SZ, OUTPUT_SZ>,
+        client_id: ClientId,
+        result: Option<EvaluationResult>,
+    ) -> Self {
         let test_inputs = [0.0; INPUT_SZ];
 
         Self {
             client_id,
+            window_id: egui::Id::new(genome.identifier()),
             genome_graph: GenomeGraph {
                 activation: genome.activate::<[f32; INPUT_SZ]>(test_inputs.clone()),
-                genome,
+                genome: genome.clone(),
+                show_disabled: false,
             },
+            genome,
+            result,
+            pruned: false,
+            layered: false,
             test_inputs,
             fd_graph: FDGraph::with_spawner(Box::new(Self::spawner), true),
+            trail: Vec::new(),
+            show_boundary: false,
+            decision_boundary: DecisionBoundary::default(),
+            test_cases: TestCases::default(),
+        }
+    }
+
+    pub fn with_test_cases(mut self, test_cases: TestCases<INPUT_SZ, OUTPUT_SZ>) -> Self {
+        self.test_cases = test_cases;
+        self
+    }
+
+    fn set_test_inputs(&mut self, test_inputs: [f32; INPUT_SZ]) {
+        self.test_inputs = test_inputs;
+        self.genome_graph.update(test_inputs);
+        // Only the inputs past the first two are taken from the test inputs
+        if INPUT_SZ > 2 {
+            self.decision_boundary.invalidate();
+        }
+    }
+
+    fn set_genome(&mut self, genome: Genome<INPUT_SZ, OUTPUT_SZ>) {
+        self.genome = genome;
+        self.update_graph();
+    }
+
+    /// Left to right, with each layer centered vertically.
+    fn layered_spawner(
+        genome: &Genome<INPUT_SZ, OUTPUT_SZ>,
+    ) -> impl FnMut(Node<INPUT_SZ, OUTPUT_SZ>) -> Option<NodeEntity> {
+        let layers = genome.layers();
+        let layer_dist = INPUT_OUTPUT_DIST / (layers.len() - 1) as f32;
+        let mut positions = vec![pos2(0.0, 0.0); genome.nodes()];
+
+        for (layer_index, layer) in layers.iter().enumerate() {
+            let node_dist = ADJACENT_NODE_DIST.min(INPUT_OUTPUT_DIST * 1.5 / layer.len() as f32);
+            let height = (layer.len() - 1) as f32 * node_dist;
+
+            for (rank, node) in layer.iter().enumerate() {
+                positions[node.0] = pos2(
+                    layer_index as f32 * layer_dist - INPUT_OUTPUT_DIST / 2.0,
+                    rank as f32 * node_dist - height / 2.0,
+                );
+            }
+        }
+
+        move |node| {
+            Some(NodeEntity {
+                pos: positions[node.0],
+                fixed: true,
+                ..Default::default()
+            })
+        }
+    }
+
+    fn update_graph(&mut self) {
+        let genome = if self.pruned {
+            self.genome.pruned()
+        } else {
+            self.genome.clone()
+        };
+
+        self.fd_graph = if self.layered {
+            FDGraph::with_spawner(Box::new(Self::layered_spawner(&genome)), true)
+        } else {
+            FDGraph::with_spawner(Box::new(Self::spawner), true)
+        };
+        self.genome_graph = GenomeGraph {
+            activation: genome.activate::<[f32; INPUT_SZ]>(self.test_inputs),
+            genome,
+            show_disabled: self.genome_graph.show_disabled,
+        };
+        self.decision_boundary.invalidate();
+    }
+
+    /// Shows the fitness, metrics, behavior and error the environment
+    /// reported, unless we've walked up to an ancestor.
+    pub fn show_evaluation(&self, ui: &mut egui::Ui) {
+        let Some(result) = self.result.as_ref().filter(|_| self.trail.is_empty()) else {
+            return;
+        };
+
+        egui::CollapsingHeader::new("Evaluation")
+            .id_source(self.window_id.with("evaluation"))
+            .show(ui, |ui| {
+                ui.label(format!("Fitness: {:.4}", result.fitness));
+                for (name, value) in &result.metrics {
+                    ui.label(format!("{}: {:.4}", name, value));
+                }
+                if let Some(behavior) = &result.behavior {
+                    ui.label(format!("Behavior: {:.2?}", behavior));
+                }
+                if let Some(error) = &result.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+            });
+    }
+
+    /// Shows how the genome does on each of the environment's test cases,
+    /// and loads a case's input into the graph when its row is clicked.
+    pub fn show_test_cases(&mut self, ui: &mut egui::Ui) {
+        if self.test_cases.cases.is_empty() {
+            return;
+        }
+
+        let format_outputs = |outputs: &[f32]| {
+            outputs
+                .iter()
+                .map(|output| format!("{:.2}", output))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let actuals = self
+            .test_cases
+            .cases
+            .iter()
+            .map(|case| self.genome_graph.genome.activate(case.input).output)
+            .collect::<Vec<_>>();
+        let errors = self
+            .test_cases
+            .cases
+            .iter()
+            .zip(&actuals)
+            .map(|(case, actual)| case.error(actual))
+            .collect::<Vec<_>>();
+        let contributions = self
+            .test_cases
+            .fitness_contributions
+            .map(|fitness_contributions| fitness_contributions(&errors));
+        let titles = ["Case", "Expected", "Actual", "Error", "Fitness"];
+        let columns = if contributions.is_some() { 5 } else { 4 };
+        let mut load = None;
+
+        egui::CollapsingHeader::new("Test cases")
+            .id_source(self.window_id.with("test_cases"))
+            .show(ui, |ui| {
+                let interact_height = ui.spacing().interact_size.y;
+
+                TableBuilder::new(ui)
+                    .striped(true)
+                    .column(Column::auto())
+                    .columns(Column::auto().at_least(40.0), columns - 1)
+                    .header(interact_height, |mut header| {
+                        for &title in &titles[..columns] {
+                            header.col(|ui| {
+                                ui.label(title);
+                            });
+                        }
+                    })
+                    .body(|mut body| {
+                        for (index, case) in self.test_cases.cases.iter().enumerate() {
+                            body.row(interact_height, |mut row| {
+                                let mut cells = vec![
+                                    row.col(|ui| {
+                                        if ui
+                                            .selectable_label(
+                                                self.test_inputs == case.input,
+                                                &case.name,
+                                            )
+                                            .clicked()
+                                        {
+                                            load = Some(case.input);
+                                        }
+                                    }),
+                                    row.col(|ui| {
+                                        ui.label(format_outputs(&case.expected));
+                                    }),
+                                    row.col(|ui| {
+                                        ui.label(format_outputs(&actuals[index]));
+                                    }),
+                                    row.col(|ui| {
+                                        ui.label(format!("{:.4}", errors[index]));
+                                    }),
+                                ];
+                                if let Some(contributions) = &contributions {
+                                    cells.push(row.col(|ui| {
+                                        ui.label(format!("{:+.4}", contributions[index]));
+                                    }));
+                                }
+
+                                // Cells only sense hovering by default
+                                for (_, cell) in cells {
+                                    if cell
+                                        .interact(egui::Sense::click())
+                                        .on_hover_cursor(egui::CursorIcon::PointingHand)
+                                        .clicked()
+                                    {
+                                        load = Some(case.input);
+                                    }
+                                }
+                            });
+                        }
+                    });
+            });
+
+        if let Some(input) = load {
+            self.set_test_inputs(input);
+        }
+    }
+
+    /// Shows where the genome came from and lets us walk up to its parents.
+    pub fn show_ancestry(&mut self, ui: &mut egui::Ui, lineage: &Lineage<INPUT_SZ, OUTPUT_SZ>) {
+        let mut walk_to = None;
+
+        egui::CollapsingHeader::new("Ancestry")
+            .id_source(self.window_id.with("ancestry"))
+            .show(ui, |ui| {
+                let Some(record) = lineage.get(self.genome.id) else {
+                    ui.label("Not recorded");
+                    return;
+                };
+
+                ui.label(format!(
+                    "{:?} in generation {}",
+                    record.origin, record.generation
+                ));
+                if !record.mutations.is_empty() {
+                    ui.label(format!("Mutations: {:?}", record.mutations));
+                }
+
+                ui.horizontal(|ui| {
+                    if !self.trail.is_empty() && ui.button("Back").clicked() {
+                        walk_to = self.trail.pop();
+                    }
+
+                    for &parent in &record.parents {
+                        match lineage
+                            .get(parent)
+                            .and_then(|record| record.genome.as_ref())
+                        {
+                            Some(genome) => {
+                                if ui.button(format!("Parent {}", parent)).clicked() {
+                                    self.trail.push(self.genome.clone());
+                                    walk_to = Some(genome.clone());
+                                }
+                            }
+                            None => {
+                                ui.add_enabled(
+                                    false,
+                                    egui::Button::new(format!("Parent {}", parent)),
+                                )
+                                .on_disabled_hover_text(
+                                    "Genomes are only kept for recent generations",
+                                );
+                            }
+                        }
+                    }
+                });
+
+                ui.label(format!(
+                    "{} recorded ancestors",
+                    lineage.ancestry(self.genome.id).len()
+                ));
+            });
+
+        if let Some(genome) = walk_to {
+            self.set_genome(genome);
         }
     }
 }
 
-impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Graph<INPUT_SZ, OUTPUT_SZ>
-    for GenomeGraph<INPUT_SZ, OUTPUT_SZ>
-{
-    fn connected(
-        &self,
-        node_1: Node<INPUT_SZ, OUTPUT_SZ>,
-        node_2: Node<INPUT_SZ, OUTPUT_SZ>,
-    ) -> bool {
-        self.genome.connections.iter().any(|connection| {
-            connection.enabled
-                && ((connection.in_node == node_1 && connection.out_node == node_2)
-                    || (connection.in_node == node_2 && connection.out_node == node_1))
-        })
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Graph for GenomeGraph<INPUT_SZ, OUTPUT_SZ> {
+    type Vertex = Node<INPUT_SZ, OUTPUT_SZ>;
+
+    fn vertices(&self) -> Vec<Self::Vertex> {
+        (0..self.genome.nodes()).map(Node).collect()
+    }
+
+    fn edges(&self) -> Vec<(Self::Vertex, Self::Vertex)> {
+        self.genome
+            .connections
+            .iter()
+            .filter(|connection| connection.enabled)
+            .map(|connection| (connection.in_node, connection.out_node))
+            .collect()
+    }
+
+    fn loose_edges(&self) -> Vec<(Self::Vertex, Self::Vertex)> {
+        if !self.show_disabled {
+            return Vec::new();
+        }
+
+        self.genome
+            .connections
+            .iter()
+            .filter(|connection| !connection.enabled)
+            .map(|connection| (connection.in_node, connection.out_node))
+            .collect()
     }
 
     fn connection_text(
@@ -101,9 +418,7 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Graph<INPUT_SZ, OUTPUT_SZ>
             .connections
             .iter()
             .find(|connection| {
-                connection.enabled
-                    && ((connection.in_node == node_1 && connection.out_node == node_2)
-                        || (connection.in_node == node_2 && connection.out_node == node_1))
+                connection.enabled && connection.in_node == node_1 && connection.out_node == node_2
             })
             .map(|connection| format!("{:.2}", connection.weight))
     }
@@ -112,8 +427,53 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Graph<INPUT_SZ, OUTPUT_SZ>
         Some(format!("{:.2}", self.activation[node]))
     }
 
-    fn size(&self) -> usize {
-        self.genome.nodes()
+    /// Blue for positive weights and red for negative ones, wider the
+    /// stronger the weight.
+    fn connection_style(
+        &self,
+        node_1: Node<INPUT_SZ, OUTPUT_SZ>,
+        node_2: Node<INPUT_SZ, OUTPUT_SZ>,
+    ) -> Option<EdgeStyle> {
+        let connection = self
+            .genome
+            .connections
+            .iter()
+            .find(|connection| connection.in_node == node_1 && connection.out_node == node_2)?;
+
+        if !connection.enabled {
+            return Some(EdgeStyle {
+                color: Some(DISABLED_CONNECTION_COLOR),
+                width: Some(MIN_CONNECTION_WIDTH),
+                dashed: true,
+            });
+        }
+
+        let strength = (connection.weight.abs() / MAX_WIDTH_WEIGHT).min(1.0);
+
+        Some(EdgeStyle {
+            color: Some(if connection.weight < 0.0 {
+                NEGATIVE_WEIGHT_COLOR
+            } else {
+                POSITIVE_WEIGHT_COLOR
+            }),
+            width: Some(
+                MIN_CONNECTION_WIDTH + (MAX_CONNECTION_WIDTH - MIN_CONNECTION_WIDTH) * strength,
+            ),
+            dashed: false,
+        })
+    }
+
+    /// Brighter the more active the node is.
+    fn node_style(&self, node: Node<INPUT_SZ, OUTPUT_SZ>) -> NodeStyle {
+        let activation = self.activation[node].clamp(0.0, 1.0);
+
+        NodeStyle {
+            color: Some(Color32::from_gray((40.0 + 215.0 * activation) as u8)),
+        }
+    }
+
+    fn directed(&self) -> bool {
+        true
     }
 }
 
@@ -122,9 +482,17 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> egui::Widget
 {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
         let mut updated = false;
+        let mut layout_changed = false;
 
         TopBottomPanel::bottom(format!("input_panel_{}", self.client_id)).show_inside(ui, |ui| {
             ui.horizontal(|ui| {
+                layout_changed |= ui.checkbox(&mut self.pruned, "Pruned").changed();
+                layout_changed |= ui.checkbox(&mut self.layered, "Layered").changed();
+                ui.checkbox(&mut self.genome_graph.show_disabled, "Disabled");
+                if INPUT_SZ >= 2 {
+                    ui.checkbox(&mut self.show_boundary, "Heatmap");
+                }
+
                 for input in self.test_inputs.iter_mut() {
                     if ui
                         .add(
@@ -141,8 +509,25 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> egui::Widget
             .response
         });
 
-        if updated {
-            self.genome_graph.update(self.test_inputs.clone());
+        if layout_changed {
+            self.update_graph();
+        } else if updated {
+            self.set_test_inputs(self.test_inputs);
+        }
+
+        if self.show_boundary {
+            TopBottomPanel::bottom(format!("decision_boundary_{}", self.client_id)).show_inside(
+                ui,
+                |ui| {
+                    self.decision_boundary.show(
+                        ui,
+                        self.window_id.with("decision_boundary"),
+                        &self.genome_graph.genome,
+                        self.test_inputs,
+                        &self.test_cases.cases,
+                    )
+                },
+            );
         }
 
         CentralPanel::default()
@@ -0,0 +1,264 @@
+use rand::Rng;
+
+use crate::{
+    client::ClientId,
+    environment::PopulationEnvironment,
+    evaluation::{Evaluation, EvaluationResult},
+    genome::Genome,
+    innovation_record::InnovationRecord,
+    lineage::Lineage,
+    population::Population,
+    speciation::Speciation,
+    species::{Species, SpeciesId},
+};
+
+/// Which islands receive an island's champion when migrating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topology {
+    /// Every island sends its champion to the next one, wrapping around.
+    Ring,
+    /// Every island sends its champion to every other island.
+    FullyConnected,
+}
+
+impl Topology {
+    pub fn neighbours(&self, island: usize, islands: usize) -> Vec<usize> {
+        match self {
+            Topology::Ring if islands > 1 => vec![(island + 1) % islands],
+            Topology::Ring => Vec::new(),
+            Topology::FullyConnected => (0..islands).filter(|&other| other != island).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Champions migrate every `interval` generations.
+    pub interval: usize,
+    pub topology: Topology,
+}
+
+/// An independently evolving population with its own speciation.
+pub struct Island<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    pub population: Population<INPUT_SZ, OUTPUT_SZ>,
+    pub last_speciation: Option<Speciation<INPUT_SZ, OUTPUT_SZ>>,
+    pub last_evaluation: Option<Evaluation>,
+    /// The members replaced by [`Island::immigrate`] since the last
+    /// evaluation.
+    immigrants: Vec<ClientId>,
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Island<INPUT_SZ, OUTPUT_SZ> {
+    pub fn new(
+        rng: &mut impl Rng,
+        innovation_record: &mut InnovationRecord<INPUT_SZ, OUTPUT_SZ>,
+        lineage: &mut Lineage<INPUT_SZ, OUTPUT_SZ>,
+        target_size: usize,
+    ) -> Self {
+        Self {
+            population: Population::new(rng, innovation_record, lineage, target_size),
+            last_speciation: None,
+            last_evaluation: None,
+            immigrants: Vec::new(),
+        }
+    }
+
+    /// Returns the species that went extinct, which are also recorded in
+    /// `lineage`.
+    pub fn evaluate_and_evolve(
+        &mut self,
+        env: &mut impl PopulationEnvironment<INPUT_SZ, OUTPUT_SZ>,
+        rng: &mut impl Rng,
+        innovation_record: &mut InnovationRecord<INPUT_SZ, OUTPUT_SZ>,
+        lineage: &mut Lineage<INPUT_SZ, OUTPUT_SZ>,
+    ) -> Vec<SpeciesId> {
+        let previous_species = self
+            .last_speciation
+            .as_ref()
+            .map(|speciation| speciation.species.keys().copied().collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        if let (Some(speciation), Some(evaluation)) =
+            (&mut self.last_speciation, &self.last_evaluation)
+        {
+            self.population
+                .evolve(evaluation, speciation, rng, innovation_record, lineage);
+        }
+        self.immigrants.clear();
+
+        let speciation = self.population.speciate(
+            lineage,
+            self.last_speciation.as_ref(),
+            self.last_evaluation.as_ref(),
+        );
+        let extinct = previous_species
+            .into_iter()
+            .filter(|species| !speciation.species.contains_key(species))
+            .collect::<Vec<_>>();
+        for &species in &extinct {
+            lineage.record_extinction(species, self.population.generation);
+        }
+
+        self.last_speciation = Some(speciation);
+        self.last_evaluation = Some(env.evaluate_population(&mut self.population));
+
+        extinct
+    }
+
+    pub fn champion(&self) -> Option<(Genome<INPUT_SZ, OUTPUT_SZ>, EvaluationResult)> {
+        self.last_evaluation.as_ref().map(|evaluation| {
+            let (champion_id, _) = evaluation.champion();
+
+            (
+                self.population.members[champion_id].clone(),
+                evaluation.results[&champion_id].clone(),
+            )
+        })
+    }
+
+    /// Replaces the least fit member that isn't already an immigrant with
+    /// `genome`. The immigrant joins the first compatible species, or founds
+    /// its own, and takes the `result` it had on its home island into the
+    /// evaluation, so it is reported and reproduces with that until it's
+    /// evaluated here.
+    pub fn immigrate(
+        &mut self,
+        genome: Genome<INPUT_SZ, OUTPUT_SZ>,
+        result: EvaluationResult,
+        lineage: &mut Lineage<INPUT_SZ, OUTPUT_SZ>,
+    ) {
+        let (Some(evaluation), Some(speciation)) =
+            (&mut self.last_evaluation, &mut self.last_speciation)
+        else {
+            return;
+        };
+        let Some(worst) = evaluation
+            .fitness
+            .iter()
+            .filter(|(id, _)| !self.immigrants.contains(id))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(id, _)| *id)
+        else {
+            return;
+        };
+
+        if let Some(species_id) = speciation.member_map.remove(&worst) {
+            let species = speciation
+                .species
+                .get_mut(&species_id)
+                .expect("Member map points to missing species");
+            species.members.retain(|&member| member != worst);
+
+            if species.members.is_empty() {
+                speciation.species.remove(&species_id);
+                lineage.record_extinction(species_id, self.population.generation);
+            }
+        }
+
+        let species_id = match speciation
+            .species
+            .values_mut()
+            .find(|species| species.is_compatible(&genome, &self.population.config))
+        {
+            Some(species) => {
+                species.members.push(worst);
+                species.id
+            }
+            None => {
+                let species_id = lineage.record_species(genome.id, self.population.generation);
+                speciation.species.insert(
+                    species_id,
+                    Species {
+                        representative: genome.clone(),
+                        members: vec![worst],
+                        id: species_id,
+                        age: 0,
+                        max_fitness: 0.0,
+                        since_last_improvement: 0,
+                    },
+                );
+                species_id
+            }
+        };
+        speciation.member_map.insert(worst, species_id);
+        lineage.record_membership(genome.id, species_id);
+
+        evaluation.insert(worst, result);
+        self.population.members[worst] = genome;
+        self.immigrants.push(worst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{connection::Connection, node::Node};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_topology() {
+        assert_eq!(Topology::Ring.neighbours(2, 3), vec![0]);
+        assert_eq!(Topology::Ring.neighbours(0, 1), Vec::<usize>::new());
+        assert_eq!(Topology::FullyConnected.neighbours(1, 3), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_immigrate_replaces_worst() {
+        let mut rng = thread_rng();
+        let mut lineage = Lineage::default();
+        let mut island =
+            Island::<2, 1>::new(&mut rng, &mut InnovationRecord::default(), &mut lineage, 3);
+        // Identical members only share a species once there's a last
+        // speciation for them to join
+        island.population.members = vec![Genome::new(); 3];
+        let first = island.population.speciate(&mut lineage, None, None);
+        island.last_speciation = Some(island.population.speciate(&mut lineage, Some(&first), None));
+        island.last_evaluation = Some(Evaluation::new(
+            [(0, 0.5.into()), (1, 0.1.into()), (2, 0.9.into())]
+                .into_iter()
+                .collect(),
+        ));
+
+        island.immigrate(
+            Genome::new(),
+            EvaluationResult::new(2.0).with_metric("accuracy", 1.0),
+            &mut lineage,
+        );
+
+        let speciation = island.last_speciation.as_ref().unwrap();
+        assert_eq!(speciation.member_map[&1], speciation.member_map[&2]);
+
+        // Too different from everyone to join a species, and the first
+        // immigrant isn't replaced
+        let mut stranger = Genome::new();
+        stranger.hidden_nodes = 7;
+        stranger.connections = (0..20)
+            .map(|innovation_number| Connection {
+                in_node: Node(1),
+                out_node: Node(3),
+                weight: 10.0,
+                enabled: true,
+                innovation_number: 100 + innovation_number,
+            })
+            .collect();
+        island.immigrate(stranger, 3.0.into(), &mut lineage);
+
+        let speciation = island.last_speciation.as_ref().unwrap();
+        let species = speciation.member_map[&0];
+        assert_eq!(island.population.members[0].hidden_nodes, 7);
+        assert_eq!(speciation.species[&species].members, vec![0]);
+        assert!(speciation
+            .species
+            .values()
+            .filter(|other| other.id != species)
+            .all(|other| !other.members.contains(&0)));
+
+        // The evaluation reports the immigrants with the results they brought
+        let evaluation = island.last_evaluation.as_ref().unwrap();
+        assert_eq!(evaluation.fitness[&1], 2.0);
+        assert_eq!(evaluation.results[&1].metrics["accuracy"], 1.0);
+        assert_eq!(evaluation.fitness[&0], 3.0);
+        assert_eq!(evaluation.fitness[&2], 0.9);
+        assert_eq!(island.champion().unwrap().1.fitness, 3.0);
+    }
+}
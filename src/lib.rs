@@ -0,0 +1,35 @@
+pub mod app;
+pub mod client;
+pub mod coevolution;
+pub mod config;
+pub mod config_editor;
+pub mod connection;
+pub mod decision_boundary;
+pub mod distributed;
+pub mod environment;
+pub mod episodic;
+pub mod evaluation;
+pub mod evaluation_manager;
+pub mod evaluator;
+pub mod evolution_thread;
+pub mod export;
+pub mod force_directed_graph;
+pub mod genome;
+pub mod genome_diff;
+pub mod genome_diff_visualizer;
+pub mod genome_layout;
+pub mod genome_pruning;
+pub mod genome_validation;
+pub mod genome_visualizer;
+pub mod innovation_record;
+pub mod island;
+pub mod lineage;
+pub mod node;
+pub mod population;
+pub mod population_manager;
+pub mod quadtree;
+pub mod reporter;
+pub mod speciation;
+pub mod species;
+pub mod species_chart;
+pub mod xor;
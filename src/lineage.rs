@@ -0,0 +1,346 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{genome::Genome, species::SpeciesId};
+
+pub type GenomeId = usize;
+
+/// How an offspring came to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// Part of the randomly generated first generation.
+    Initial,
+    /// An unchanged copy of its species' champion.
+    Elite,
+    /// Crossover of two members of the same species.
+    Crossover,
+    /// Crossover with a member of another species.
+    InterspeciesCrossover,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutation {
+    Weights,
+    NewConnection,
+    EnableConnection,
+    NewNode,
+}
+
+#[derive(Debug, Clone)]
+pub struct LineageRecord<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    pub generation: usize,
+    pub origin: Origin,
+    /// The fitter parent comes first.
+    pub parents: Vec<GenomeId>,
+    pub mutations: Vec<Mutation>,
+    /// Only kept while the genome is recent, alive or the champion, see
+    /// [`Lineage::forget_genomes`].
+    pub genome: Option<Genome<INPUT_SZ, OUTPUT_SZ>>,
+    /// The species the genome was last placed in.
+    pub species: Option<SpeciesId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpeciesRecord {
+    pub birth_generation: usize,
+    /// The species of the founding member's parents.
+    pub parents: Vec<SpeciesId>,
+    /// The first generation the species was no longer around for.
+    pub extinction_generation: Option<usize>,
+}
+
+/// Every genome and species that has been part of a run, and where they came
+/// from.
+#[derive(Default)]
+pub struct Lineage<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    next_id: GenomeId,
+    records: HashMap<GenomeId, LineageRecord<INPUT_SZ, OUTPUT_SZ>>,
+    next_species_id: SpeciesId,
+    species: HashMap<SpeciesId, SpeciesRecord>,
+    /// The records that still have their genome.
+    stored: HashSet<GenomeId>,
+    /// Records added or changed since the last [`Lineage::take_changes`].
+    changed: HashSet<GenomeId>,
+    changed_species: HashSet<SpeciesId>,
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Lineage<INPUT_SZ, OUTPUT_SZ> {
+    /// Assigns `genome` a new ID and records where it came from.
+    pub fn record(
+        &mut self,
+        genome: &mut Genome<INPUT_SZ, OUTPUT_SZ>,
+        generation: usize,
+        origin: Origin,
+        parents: Vec<GenomeId>,
+    ) {
+        genome.id = self.next_id;
+        self.next_id += 1;
+        self.changed.insert(genome.id);
+        self.stored.insert(genome.id);
+
+        self.records.insert(
+            genome.id,
+            LineageRecord {
+                generation,
+                origin,
+                parents,
+                mutations: Vec::new(),
+                genome: Some(genome.clone()),
+                species: None,
+            },
+        );
+    }
+
+    /// Records mutations applied to an already recorded genome, which is
+    /// stored again in its mutated form.
+    pub fn record_mutations(
+        &mut self,
+        genome: &Genome<INPUT_SZ, OUTPUT_SZ>,
+        mutations: Vec<Mutation>,
+    ) {
+        if let Some(record) = self.records.get_mut(&genome.id) {
+            record.mutations.extend(mutations);
+            record.genome = Some(genome.clone());
+            self.changed.insert(genome.id);
+            self.stored.insert(genome.id);
+        }
+    }
+
+    /// Drops the genomes of every record from before generation `since` that
+    /// isn't in `keep`, leaving only where they came from, so a long run
+    /// doesn't hold on to every genome it ever made.
+    pub fn forget_genomes(&mut self, since: usize, keep: &HashSet<GenomeId>) {
+        for id in std::mem::take(&mut self.stored) {
+            let Some(record) = self.records.get_mut(&id) else {
+                continue;
+            };
+
+            if record.generation >= since || keep.contains(&id) {
+                self.stored.insert(id);
+            } else {
+                record.genome = None;
+                self.changed.insert(id);
+            }
+        }
+    }
+
+    pub fn get(&self, id: GenomeId) -> Option<&LineageRecord<INPUT_SZ, OUTPUT_SZ>> {
+        self.records.get(&id)
+    }
+
+    /// Assigns a new species, founded by `founder`, the next ID. Its parents
+    /// are the species `founder`'s parents were placed in.
+    pub fn record_species(&mut self, founder: GenomeId, generation: usize) -> SpeciesId {
+        let id = self.next_species_id;
+        self.next_species_id += 1;
+        self.changed_species.insert(id);
+
+        let mut parents = Vec::new();
+        for &parent in self
+            .get(founder)
+            .map(|record| &record.parents[..])
+            .unwrap_or(&[])
+        {
+            if let Some(species) = self.get(parent).and_then(|record| record.species) {
+                if !parents.contains(&species) {
+                    parents.push(species);
+                }
+            }
+        }
+
+        self.species.insert(
+            id,
+            SpeciesRecord {
+                birth_generation: generation,
+                parents,
+                extinction_generation: None,
+            },
+        );
+
+        id
+    }
+
+    pub fn record_membership(&mut self, genome: GenomeId, species: SpeciesId) {
+        if let Some(record) = self.records.get_mut(&genome) {
+            record.species = Some(species);
+            self.changed.insert(genome);
+        }
+    }
+
+    pub fn record_extinction(&mut self, species: SpeciesId, generation: usize) {
+        if let Some(record) = self.species.get_mut(&species) {
+            record.extinction_generation = Some(generation);
+            self.changed_species.insert(species);
+        }
+    }
+
+    pub fn get_species(&self, id: SpeciesId) -> Option<&SpeciesRecord> {
+        self.species.get(&id)
+    }
+
+    /// A lineage of only the records that were added or changed since the
+    /// last call, for keeping a copy up to date with [`Lineage::merge`].
+    pub fn take_changes(&mut self) -> Lineage<INPUT_SZ, OUTPUT_SZ> {
+        let changed = std::mem::take(&mut self.changed);
+        let changed_species = std::mem::take(&mut self.changed_species);
+
+        Lineage {
+            next_id: self.next_id,
+            records: changed
+                .into_iter()
+                .map(|id| (id, self.records[&id].clone()))
+                .collect(),
+            next_species_id: self.next_species_id,
+            species: changed_species
+                .into_iter()
+                .map(|id| (id, self.species[&id].clone()))
+                .collect(),
+            stored: HashSet::new(),
+            changed: HashSet::new(),
+            changed_species: HashSet::new(),
+        }
+    }
+
+    /// Applies changes from [`Lineage::take_changes`]. They aren't recorded
+    /// as changes of this lineage, since a copy isn't passed on any further.
+    pub fn merge(&mut self, changes: Lineage<INPUT_SZ, OUTPUT_SZ>) {
+        self.next_id = self.next_id.max(changes.next_id);
+        self.next_species_id = self.next_species_id.max(changes.next_species_id);
+        self.records.extend(changes.records);
+        self.species.extend(changes.species);
+    }
+
+    /// All recorded ancestors of `id`, closest first.
+    pub fn ancestry(&self, id: GenomeId) -> Vec<GenomeId> {
+        let mut ancestors = Vec::new();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([id]);
+
+        while let Some(id) = queue.pop_front() {
+            for &parent in self
+                .get(id)
+                .map(|record| &record.parents[..])
+                .unwrap_or(&[])
+            {
+                if seen.insert(parent) {
+                    ancestors.push(parent);
+                    queue.push_back(parent);
+                }
+            }
+        }
+
+        ancestors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ancestry() {
+        let mut lineage = Lineage::<2, 1>::default();
+        let mut a = Genome::new();
+        let mut b = Genome::new();
+        let mut c = Genome::new();
+        let mut d = Genome::new();
+
+        lineage.record(&mut a, 1, Origin::Initial, vec![]);
+        lineage.record(&mut b, 1, Origin::Initial, vec![]);
+        lineage.record(&mut c, 2, Origin::Crossover, vec![a.id, b.id]);
+        lineage.record(&mut d, 3, Origin::Crossover, vec![c.id, a.id]);
+        lineage.record_mutations(&d, vec![Mutation::NewNode]);
+
+        assert_eq!(lineage.ancestry(d.id), vec![c.id, a.id, b.id]);
+        assert_eq!(lineage.ancestry(a.id), vec![]);
+        assert_eq!(
+            lineage.get(d.id).unwrap().mutations,
+            vec![Mutation::NewNode]
+        );
+    }
+
+    #[test]
+    fn test_species() {
+        let mut lineage = Lineage::<2, 1>::default();
+        let mut a = Genome::new();
+        let mut b = Genome::new();
+        let mut c = Genome::new();
+
+        lineage.record(&mut a, 1, Origin::Initial, vec![]);
+        lineage.record(&mut b, 1, Origin::Initial, vec![]);
+        let first = lineage.record_species(a.id, 1);
+        let second = lineage.record_species(b.id, 1);
+        lineage.record_membership(a.id, first);
+        lineage.record_membership(b.id, second);
+
+        lineage.record(&mut c, 2, Origin::InterspeciesCrossover, vec![b.id, a.id]);
+        let third = lineage.record_species(c.id, 2);
+        lineage.record_extinction(first, 3);
+
+        assert_eq!((first, second, third), (0, 1, 2));
+        assert_eq!(
+            lineage.get_species(third),
+            Some(&SpeciesRecord {
+                birth_generation: 2,
+                parents: vec![second, first],
+                extinction_generation: None,
+            })
+        );
+        assert_eq!(
+            lineage.get_species(first).unwrap().extinction_generation,
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_take_changes() {
+        let mut lineage = Lineage::<2, 1>::default();
+        let mut a = Genome::new();
+        let mut b = Genome::new();
+
+        lineage.record(&mut a, 1, Origin::Initial, vec![]);
+        let species = lineage.record_species(a.id, 1);
+        let mut copy = Lineage::default();
+        copy.merge(lineage.take_changes());
+
+        lineage.record(&mut b, 2, Origin::Elite, vec![a.id]);
+        lineage.record_extinction(species, 3);
+        let changes = lineage.take_changes();
+        assert_eq!(changes.records.len(), 1);
+        copy.merge(changes);
+
+        assert_eq!(copy.ancestry(b.id), vec![a.id]);
+        assert_eq!(
+            copy.get_species(species).unwrap().extinction_generation,
+            Some(3)
+        );
+        assert!(lineage.take_changes().records.is_empty());
+        assert!(copy.take_changes().records.is_empty());
+    }
+
+    #[test]
+    fn test_forget_genomes() {
+        let mut lineage = Lineage::<2, 1>::default();
+        let mut a = Genome::new();
+        let mut b = Genome::new();
+        let mut c = Genome::new();
+        let mut d = Genome::new();
+
+        lineage.record(&mut a, 1, Origin::Initial, vec![]);
+        lineage.record(&mut b, 1, Origin::Initial, vec![]);
+        lineage.record(&mut c, 2, Origin::Elite, vec![a.id]);
+        lineage.record(&mut d, 3, Origin::Elite, vec![c.id]);
+        let mut copy = Lineage::default();
+        copy.merge(lineage.take_changes());
+
+        lineage.forget_genomes(2, &HashSet::from([b.id]));
+        copy.merge(lineage.take_changes());
+
+        for lineage in [&lineage, &copy] {
+            assert!(lineage.get(a.id).unwrap().genome.is_none());
+            assert!(lineage.get(b.id).unwrap().genome.is_some());
+            assert!(lineage.get(c.id).unwrap().genome.is_some());
+            assert!(lineage.get(d.id).unwrap().genome.is_some());
+            assert_eq!(lineage.ancestry(d.id), vec![c.id, a.id]);
+        }
+    }
+}
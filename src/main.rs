@@ -1,58 +1,10 @@
-mod app;
-mod client;
-mod connection;
-mod environment;
-mod evaluation;
-mod evaluation_manager;
-mod evaluator;
-mod force_directed_graph;
-mod genome;
-mod genome_visualizer;
-mod innovation_record;
-mod node;
-mod population;
-mod population_manager;
-mod speciation;
-mod species;
-
+use ai_world::{
+    evaluation_manager::EvaluationManager,
+    evaluator::Evaluator,
+    force_directed_graph::{FDGraph, Graph},
+    xor::XOREnv,
+};
 use eframe::egui::{self, Response};
-use environment::Environment;
-use evaluation_manager::EvaluationManager;
-use evaluator::Evaluator;
-use force_directed_graph::{FDGraph, Graph};
-use genome::Genome;
-
-const DATA: [([f32; 2], [f32; 1]); 4] = [
-    ([0.0, 0.0], [0.0]),
-    ([1.0, 0.0], [1.0]),
-    ([0.0, 1.0], [1.0]),
-    ([1.0, 1.0], [0.0]),
-];
-
-struct XOREnv;
-
-impl Environment<2, 1> for XOREnv {
-    // fn evaluate(&mut self, genome: &mut Genome<2, 1>) -> f32 {
-    //     let mut fitness = 4.0;
-    //
-    //     for (input, output) in DATA {
-    //         let diff = genome.activate::<[f32; 2], [f32; 1]>(input)[0] - output[0];
-    //         fitness -= diff * diff;
-    //     }
-    //
-    //     fitness
-    // }
-    fn evaluate(&mut self, genome: &mut Genome<2, 1>) -> f32 {
-        let mut error = 0.0;
-
-        for (input, output) in DATA {
-            let diff = genome.activate::<[f32; 2]>(input).output[0] - output[0];
-            error += diff * diff;
-        }
-
-        1.0 - (error / DATA.len() as f32).sqrt()
-    }
-}
 
 fn main() {
     tracing_subscriber::fmt::init();
@@ -76,18 +28,20 @@ fn main() {
 
 struct TestGraph;
 
-impl Graph<2, 2> for TestGraph {
-    fn connected(&self, node_1: node::Node<2, 2>, node_2: node::Node<2, 2>) -> bool {
-        node_1.0 == 0 && node_2.0 == 1 || node_1.0 == 1 && node_2.0 == 0
+impl Graph for TestGraph {
+    type Vertex = usize;
+
+    fn vertices(&self) -> Vec<usize> {
+        (0..4).collect()
     }
 
-    fn size(&self) -> usize {
-        4
+    fn edges(&self) -> Vec<(usize, usize)> {
+        vec![(0, 1)]
     }
 }
 
 struct GraphTestingApp {
-    force_directed_graph: FDGraph<2, 2>,
+    force_directed_graph: FDGraph<usize>,
 }
 
 impl eframe::App for GraphTestingApp {
@@ -1,51 +1,53 @@
 use crate::{
+    config::NeatConfig,
     environment::Environment,
     evaluation::Evaluation,
     genome::Genome,
     innovation_record::InnovationRecord,
+    lineage::{Lineage, Origin},
     speciation::Speciation,
     species::{Species, SpeciesId},
 };
 use rand::{seq::SliceRandom, thread_rng, Rng};
 use std::{collections::HashMap, iter};
 
-const INTERSPECIE_MATE_PROB: f64 = 0.003;
-const SURVIVAL_THRESHOLD: f32 = 0.2;
-const MUTATION_PROB: f64 = 0.2;
-const STAGNANT_THRESHOLD: usize = 15;
-
 #[derive(Clone)]
 pub struct Population<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
     pub members: Vec<Genome<INPUT_SZ, OUTPUT_SZ>>,
     pub target_size: usize,
     pub generation: usize,
+    pub config: NeatConfig,
 }
 
 impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Population<INPUT_SZ, OUTPUT_SZ> {
     pub fn new(
         rng: &mut impl Rng,
         innovation_record: &mut InnovationRecord<INPUT_SZ, OUTPUT_SZ>,
+        lineage: &mut Lineage<INPUT_SZ, OUTPUT_SZ>,
         target_size: usize,
     ) -> Self {
         Self {
             generation: 0,
             target_size,
+            config: NeatConfig::default(),
             members: (0..target_size)
-                .map(|_| Genome::new_random_initial(rng, innovation_record))
+                .map(|_| {
+                    let mut genome = Genome::new_random_initial(rng, innovation_record);
+                    lineage.record(&mut genome, 1, Origin::Initial, Vec::new());
+                    genome
+                })
                 .collect(),
         }
     }
 
     pub fn evaluate(&mut self, env: &mut impl Environment<INPUT_SZ, OUTPUT_SZ>) -> Evaluation {
-        Evaluation {
-            fitness: self
-                .members
+        Evaluation::new(
+            self.members
                 .iter_mut()
                 .map(|member| env.evaluate(member))
                 .enumerate()
                 .collect(),
-            adjusted: false,
-        }
+        )
     }
 
     // See https://neat-python.readthedocs.io/en/latest/_modules/reproduction.html
@@ -101,30 +103,48 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Population<INPUT_SZ, OUTPUT_
         offspring
     }
 
-    fn breed(&self, evaluation: &Evaluation, selection: &[usize]) -> Genome<INPUT_SZ, OUTPUT_SZ> {
+    fn breed(
+        &self,
+        evaluation: &Evaluation,
+        selection: &[usize],
+        lineage: &mut Lineage<INPUT_SZ, OUTPUT_SZ>,
+    ) -> Genome<INPUT_SZ, OUTPUT_SZ> {
         // TODO: I usually like to pass down rngs, but there were some type
         // errors
         let mut rng = thread_rng();
 
         let parent_1 = *selection.choose(&mut rng).unwrap();
-        let parent_2 = if rng.gen_bool(INTERSPECIE_MATE_PROB) {
+        let (parent_2, origin) = if rng.gen_bool(self.config.interspecies_mate_prob) {
             // FIXME: This should only choose from the survivors
-            rng.gen_range(0..self.members.len())
+            (
+                rng.gen_range(0..self.members.len()),
+                Origin::InterspeciesCrossover,
+            )
         } else {
-            *selection.choose(&mut rng).unwrap()
+            (*selection.choose(&mut rng).unwrap(), Origin::Crossover)
         };
 
-        if evaluation.fitness[&parent_1] > evaluation.fitness[&parent_2] {
-            Genome::crossover(&self.members[parent_1], &self.members[parent_2], &mut rng)
+        let (fitter, other) = if evaluation.fitness[&parent_1] > evaluation.fitness[&parent_2] {
+            (&self.members[parent_1], &self.members[parent_2])
         } else {
-            Genome::crossover(&self.members[parent_2], &self.members[parent_1], &mut rng)
-        }
+            (&self.members[parent_2], &self.members[parent_1])
+        };
+
+        let mut offspring = Genome::crossover(fitter, other, &mut rng);
+        lineage.record(
+            &mut offspring,
+            self.generation + 1,
+            origin,
+            vec![fitter.id, other.id],
+        );
+
+        offspring
     }
 
     pub fn kill_stagnant_species(&mut self, speciation: &mut Speciation<INPUT_SZ, OUTPUT_SZ>) {
         speciation
             .species
-            .retain(|_, species| species.since_last_improvement < STAGNANT_THRESHOLD)
+            .retain(|_, species| species.since_last_improvement < self.config.stagnation_limit)
         // speciation
         //     .species
         //     .retain(|_, species| species.age == 0 || species.members.len() > 1);
@@ -134,6 +154,7 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Population<INPUT_SZ, OUTPUT_
         &mut self,
         evaluation: &Evaluation,
         speciation: &mut Speciation<INPUT_SZ, OUTPUT_SZ>,
+        lineage: &mut Lineage<INPUT_SZ, OUTPUT_SZ>,
     ) {
         let offspring = self.compute_offspring(evaluation, speciation);
         // let total_average_species_adjusted_fitnesss = speciation
@@ -148,7 +169,7 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Population<INPUT_SZ, OUTPUT_
             .flat_map(|species| {
                 species.sort_by_fitness(evaluation);
                 let survivor_count =
-                    (species.members.len() as f32 * SURVIVAL_THRESHOLD).ceil() as usize;
+                    (species.members.len() as f32 * self.config.survival_threshold).ceil() as usize;
 
                 // There should be at least two parents
                 let survivor_count = survivor_count.max(2).min(species.members.len());
@@ -163,11 +184,18 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Population<INPUT_SZ, OUTPUT_
                 //     .round() as usize;
 
                 let champion_id = evaluation.species_champion(species).0;
-                let champion_genome = self.members[champion_id].clone();
+                let mut champion_genome = self.members[champion_id].clone();
+                lineage.record(
+                    &mut champion_genome,
+                    self.generation + 1,
+                    Origin::Elite,
+                    vec![self.members[champion_id].id],
+                );
 
-                iter::repeat_with(|| self.breed(evaluation, survivors))
+                iter::repeat_with(|| self.breed(evaluation, survivors, lineage))
                     .take(offspring - 1)
                     .chain(iter::once(champion_genome))
+                    .collect::<Vec<_>>()
             })
             .collect::<Vec<_>>();
 
@@ -178,10 +206,12 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Population<INPUT_SZ, OUTPUT_
         &mut self,
         rng: &mut impl Rng,
         innovation_record: &mut InnovationRecord<INPUT_SZ, OUTPUT_SZ>,
+        lineage: &mut Lineage<INPUT_SZ, OUTPUT_SZ>,
     ) {
         for member in &mut self.members {
-            if rng.gen_bool(MUTATION_PROB) {
-                member.mutate(rng, innovation_record);
+            if rng.gen_bool(self.config.mutation_prob) {
+                let mutations = member.mutate(rng, innovation_record, &self.config);
+                lineage.record_mutations(member, mutations);
             }
         }
     }
@@ -191,15 +221,16 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Population<INPUT_SZ, OUTPUT_
         speciation: &mut Speciation<INPUT_SZ, OUTPUT_SZ>,
         rng: &mut impl Rng,
         innovation_record: &mut InnovationRecord<INPUT_SZ, OUTPUT_SZ>,
+        lineage: &mut Lineage<INPUT_SZ, OUTPUT_SZ>,
     ) {
         self.kill_stagnant_species(speciation);
-        self.reproduce(evaluation, speciation);
-        self.mutate(rng, innovation_record);
+        self.reproduce(evaluation, speciation, lineage);
+        self.mutate(rng, innovation_record, lineage);
     }
 
     pub fn speciate(
         &mut self,
-        rng: &mut impl Rng,
+        lineage: &mut Lineage<INPUT_SZ, OUTPUT_SZ>,
         last_speciation: Option<&Speciation<INPUT_SZ, OUTPUT_SZ>>,
         last_evaluation: Option<&Evaluation>,
     ) -> Speciation<INPUT_SZ, OUTPUT_SZ> {
@@ -212,7 +243,7 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Population<INPUT_SZ, OUTPUT_
                 last_speciation.and_then(|Speciation { species, .. }| {
                     species
                         .values()
-                        .find(|species| species.is_compatible(&member))
+                        .find(|species| species.is_compatible(member, &self.config))
                 })
             {
                 if let Some(compatible_species) = species.get_mut(&last_species.id) {
@@ -240,8 +271,9 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Population<INPUT_SZ, OUTPUT_
                 }
 
                 member_map.insert(member_id, last_species.id);
+                lineage.record_membership(member.id, last_species.id);
             } else {
-                let species_id = rng.gen();
+                let species_id = lineage.record_species(member.id, self.generation);
 
                 species.insert(
                     species_id,
@@ -256,6 +288,7 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Population<INPUT_SZ, OUTPUT_
                 );
 
                 member_map.insert(member_id, species_id);
+                lineage.record_membership(member.id, species_id);
             }
         }
 
@@ -1,10 +1,15 @@
 use crate::{
+    client::ClientId,
+    environment::TestCases,
     evaluation::Evaluation,
     force_directed_graph::{FDGraph, Graph},
+    genome_diff_visualizer::GenomeDiffVisualizer,
     genome_visualizer::GenomeVisualizer,
-    node::Node,
+    lineage::Lineage,
     population::Population,
     speciation::Speciation,
+    species::SpeciesId,
+    species_chart::species_color,
 };
 use eframe::{egui, epaint::Vec2};
 use egui_extras::{Column, TableBuilder};
@@ -13,33 +18,77 @@ const GENOME_WINDOW_SIZE: f32 = 350.0;
 
 #[derive(Default)]
 pub struct PopulationManager<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
-    fd_graph: FDGraph<INPUT_SZ, OUTPUT_SZ>,
+    fd_graph: FDGraph<ClientId>,
     genomes_open: Vec<GenomeVisualizer<INPUT_SZ, OUTPUT_SZ>>,
+    diff_open: bool,
+    /// Handed to every [`GenomeVisualizer`] that's opened.
+    test_cases: TestCases<INPUT_SZ, OUTPUT_SZ>,
     // genome_hovered: Option<GenomeVisualizer<INPUT_SZ, OUTPUT_SZ>>,
 }
 
 impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> PopulationManager<INPUT_SZ, OUTPUT_SZ> {
+    pub fn new(test_cases: TestCases<INPUT_SZ, OUTPUT_SZ>) -> Self {
+        Self {
+            test_cases,
+            ..Default::default()
+        }
+    }
+
+    pub fn open_species_champion(
+        &mut self,
+        population: &Population<INPUT_SZ, OUTPUT_SZ>,
+        speciation: &Speciation<INPUT_SZ, OUTPUT_SZ>,
+        evaluation: &Evaluation,
+        species: SpeciesId,
+    ) {
+        let Some(species) = speciation.species.get(&species) else {
+            return;
+        };
+        let champion = evaluation.species_champion(species).0;
+
+        self.genomes_open.push(
+            GenomeVisualizer::new(
+                population.members[champion].clone(),
+                champion,
+                evaluation.results.get(&champion).cloned(),
+            )
+            .with_test_cases(self.test_cases.clone()),
+        );
+    }
+
     pub fn show(
         &mut self,
         ctx: &egui::Context,
         population: &mut Population<INPUT_SZ, OUTPUT_SZ>,
         speciation: &Speciation<INPUT_SZ, OUTPUT_SZ>,
         evaluation: &Evaluation,
+        lineage: &Lineage<INPUT_SZ, OUTPUT_SZ>,
     ) {
         egui::SidePanel::left("Population controls").show(ctx, |ui| {
             ui.label(format!("Generation: {}", population.generation));
             ui.label(format!("Target size: {}", population.target_size));
             ui.label(format!("Size: {}", population.members.len()));
             ui.label(format!("Species: {}", speciation.species.len()));
+            for name in evaluation.metric_names() {
+                if let Some(average) = evaluation.average_metric(name) {
+                    ui.label(format!("Average {}: {:.4}", name, average));
+                }
+            }
+            if evaluation.invalid_count() > 0 {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("Invalid: {}", evaluation.invalid_count()),
+                );
+            }
             if let (Some(vis_1), Some(vis_2)) = (self.genomes_open.get(0), self.genomes_open.get(1))
             {
-                ui.label(format!(
-                    "Distance: {}",
-                    vis_1
-                        .genome_graph
-                        .genome
-                        .distance(&vis_2.genome_graph.genome)
-                ));
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "Distance: {}",
+                        vis_1.genome.distance(&vis_2.genome)
+                    ));
+                    ui.toggle_value(&mut self.diff_open, "Compare");
+                });
             }
 
             ui.separator();
@@ -70,13 +119,24 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> PopulationManager<INPUT_SZ,
                     for species in speciation.species.values() {
                         body.row(interact_height, |mut row| {
                             row.col(|ui| {
-                                let res = ui.button(&species.id.to_string()[..6]);
+                                let res = ui.button(
+                                    egui::RichText::new(species.id.to_string())
+                                        .color(species_color(species.id)),
+                                );
+                                let res = match lineage.get_species(species.id) {
+                                    Some(record) if record.parents.is_empty() => res.on_hover_text(
+                                        format!("Born in generation {}", record.birth_generation),
+                                    ),
+                                    Some(record) => res.on_hover_text(format!(
+                                        "Born in generation {} from species {:?}",
+                                        record.birth_generation, record.parents
+                                    )),
+                                    None => res,
+                                };
                                 if res.clicked() {
-                                    let champion = evaluation.species_champion(species).0;
-                                    let genome = population.members[champion].clone();
-
-                                    self.genomes_open
-                                        .push(GenomeVisualizer::new(genome, champion));
+                                    self.open_species_champion(
+                                        population, speciation, evaluation, species.id,
+                                    );
                                 }
 
                                 // if !res
@@ -119,19 +179,40 @@ impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> PopulationManager<INPUT_SZ,
             self.fd_graph.show(ui, &pop_graph, |client_id| {
                 let genome = population.members[client_id].clone();
 
-                self.genomes_open
-                    .push(GenomeVisualizer::new(genome, client_id));
+                self.genomes_open.push(
+                    GenomeVisualizer::new(
+                        genome,
+                        client_id,
+                        evaluation.results.get(&client_id).cloned(),
+                    )
+                    .with_test_cases(self.test_cases.clone()),
+                );
             });
         });
 
+        if let (Some(vis_1), Some(vis_2)) = (self.genomes_open.first(), self.genomes_open.get(1)) {
+            egui::Window::new("Genome diff")
+                .open(&mut self.diff_open)
+                .show(ctx, |ui| {
+                    ui.add(GenomeDiffVisualizer {
+                        this: &vis_1.genome,
+                        other: &vis_2.genome,
+                    });
+                });
+        }
+
         self.genomes_open
             .retain_mut(|visualizer: &mut GenomeVisualizer<INPUT_SZ, OUTPUT_SZ>| {
                 let mut open = true;
 
-                egui::Window::new(visualizer.genome_graph.genome.identifier())
+                egui::Window::new(visualizer.genome.identifier())
+                    .id(visualizer.window_id)
                     .default_size(Vec2::splat(GENOME_WINDOW_SIZE))
                     .open(&mut open)
                     .show(ctx, |ui| {
+                        visualizer.show_evaluation(ui);
+                        visualizer.show_test_cases(ui);
+                        visualizer.show_ancestry(ui, lineage);
                         ui.add(visualizer);
                     });
 
@@ -144,18 +225,30 @@ struct PopulationGraph<'a, const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
     speciation: &'a Speciation<INPUT_SZ, OUTPUT_SZ>,
 }
 
-impl<'a, const INPUT_SZ: usize, const OUTPUT_SZ: usize> Graph<INPUT_SZ, OUTPUT_SZ>
+impl<'a, const INPUT_SZ: usize, const OUTPUT_SZ: usize> Graph
     for PopulationGraph<'a, INPUT_SZ, OUTPUT_SZ>
 {
-    fn connected(
-        &self,
-        node_1: Node<INPUT_SZ, OUTPUT_SZ>,
-        node_2: Node<INPUT_SZ, OUTPUT_SZ>,
-    ) -> bool {
-        self.speciation.member_map[&node_1.0] == self.speciation.member_map[&node_2.0]
+    type Vertex = ClientId;
+
+    fn vertices(&self) -> Vec<ClientId> {
+        let mut members = self
+            .speciation
+            .member_map
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        members.sort_unstable();
+        members
     }
 
-    fn size(&self) -> usize {
-        self.speciation.member_map.len()
+    /// Connects every member to the first member of its species, which is
+    /// enough to pull a species together without an edge for every pair.
+    fn edges(&self) -> Vec<(ClientId, ClientId)> {
+        self.speciation
+            .species
+            .values()
+            .filter_map(|species| species.members.split_first())
+            .flat_map(|(&first, rest)| rest.iter().map(move |&member| (first, member)))
+            .collect()
     }
 }
@@ -0,0 +1,152 @@
+use eframe::epaint::{Pos2, Rect, Vec2};
+
+/// Stops points on top of each other from splitting forever.
+const MAX_DEPTH: usize = 16;
+
+struct Cell {
+    /// The width of the cell's square.
+    size: f32,
+    /// How many points are in the cell.
+    mass: f32,
+    center_of_mass: Pos2,
+    children: Vec<usize>,
+}
+
+/// Groups points so the pull or push of far away groups can be approximated
+/// by their center of mass, the Barnes-Hut approximation.
+pub struct QuadTree {
+    /// The root is first.
+    cells: Vec<Cell>,
+}
+
+impl QuadTree {
+    pub fn new(points: &[Pos2]) -> Self {
+        let mut tree = Self { cells: Vec::new() };
+
+        if let Some(bounds) = points
+            .iter()
+            .map(|point| Rect::from_center_size(*point, Vec2::ZERO))
+            .reduce(|bounds, rect| bounds.union(rect))
+        {
+            let size = bounds.width().max(bounds.height());
+            let square = Rect::from_min_size(bounds.min, Vec2::splat(size));
+            tree.build(points.to_vec(), square, 0);
+        }
+
+        tree
+    }
+
+    fn build(&mut self, points: Vec<Pos2>, bounds: Rect, depth: usize) -> usize {
+        let index = self.cells.len();
+        let mass = points.len() as f32;
+        let center_of_mass = (points
+            .iter()
+            .fold(Vec2::ZERO, |sum, point| sum + point.to_vec2())
+            / mass)
+            .to_pos2();
+
+        self.cells.push(Cell {
+            size: bounds.width(),
+            mass,
+            center_of_mass,
+            children: Vec::new(),
+        });
+
+        if points.len() > 1 && depth < MAX_DEPTH {
+            let center = bounds.center();
+            let mut quadrants = [Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+
+            for point in points {
+                let quadrant = (point.x >= center.x) as usize + 2 * (point.y >= center.y) as usize;
+                quadrants[quadrant].push(point);
+            }
+
+            for (quadrant, points) in quadrants.into_iter().enumerate() {
+                if points.is_empty() {
+                    continue;
+                }
+
+                let min = Pos2::new(
+                    if quadrant % 2 == 0 {
+                        bounds.min.x
+                    } else {
+                        center.x
+                    },
+                    if quadrant < 2 { bounds.min.y } else { center.y },
+                );
+                let child = self.build(
+                    points,
+                    Rect::from_min_size(min, bounds.size() / 2.0),
+                    depth + 1,
+                );
+                self.cells[index].children.push(child);
+            }
+        }
+
+        index
+    }
+
+    /// The sum of `strength / dist²` pushing `pos` away from every point.
+    /// Groups that look smaller than `theta` from `pos` are treated as one
+    /// point, so 0 is exact and larger is faster.
+    pub fn repulsion(&self, pos: Pos2, strength: f32, theta: f32) -> Vec2 {
+        let mut force = Vec2::ZERO;
+        let mut stack = if self.cells.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+
+        while let Some(index) = stack.pop() {
+            let cell = &self.cells[index];
+            let dist = pos.distance(cell.center_of_mass);
+
+            if cell.children.is_empty() || cell.size < theta * dist {
+                // Including the point itself
+                if dist > 0.0 {
+                    force += (pos - cell.center_of_mass).normalized() * strength * cell.mass
+                        / (dist * dist);
+                }
+            } else {
+                stack.extend(&cell.children);
+            }
+        }
+
+        force
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::epaint::pos2;
+
+    #[test]
+    fn test_repulsion() {
+        let points = (0..50)
+            .map(|i| pos2((i * 7 % 13) as f32, (i * 5 % 11) as f32 * 1.5))
+            .chain([pos2(3.0, 3.0), pos2(3.0, 3.0)])
+            .collect::<Vec<_>>();
+        let tree = QuadTree::new(&points);
+
+        for &pos in &points {
+            let forces = points
+                .iter()
+                .filter(|other| other.distance(pos) > 0.0)
+                .map(|other| (pos - *other).normalized() * 2.0 / pos.distance_sq(*other))
+                .collect::<Vec<_>>();
+            let exact = forces.iter().fold(Vec2::ZERO, |sum, force| sum + *force);
+            // The forces can mostly cancel out, so the error is relative to
+            // how much pushing there is overall
+            let total = forces.iter().map(|force| force.length()).sum::<f32>();
+
+            assert!((tree.repulsion(pos, 2.0, 0.0) - exact).length() < 1e-3);
+            assert!((tree.repulsion(pos, 2.0, 0.5) - exact).length() < 0.05 * total);
+        }
+
+        assert_eq!(
+            QuadTree::new(&[]).repulsion(Pos2::ZERO, 1.0, 0.5),
+            Vec2::ZERO
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use std::io::Write;
+
+use crate::{evaluation::Evaluation, genome::Genome, speciation::Speciation, species::SpeciesId};
+
+/// Observes a run generation by generation. Callbacks that concern a single
+/// population also receive the index of its island.
+pub trait Reporter<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
+    fn on_generation_start(&mut self, _generation: usize) {}
+
+    fn on_speciated(&mut self, _island: usize, _speciation: &Speciation<INPUT_SZ, OUTPUT_SZ>) {}
+
+    fn on_evaluated(&mut self, _island: usize, _evaluation: &Evaluation) {}
+
+    fn on_species_extinct(&mut self, _island: usize, _species: SpeciesId) {}
+
+    /// Called whenever a genome beats the best fitness seen so far in the run.
+    fn on_new_champion(
+        &mut self,
+        _island: usize,
+        _genome: &Genome<INPUT_SZ, OUTPUT_SZ>,
+        _fitness: f32,
+    ) {
+    }
+}
+
+/// Prints a summary of every generation.
+#[derive(Default)]
+pub struct StdoutReporter {
+    generation: usize,
+    species: usize,
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Reporter<INPUT_SZ, OUTPUT_SZ>
+    for StdoutReporter
+{
+    fn on_generation_start(&mut self, generation: usize) {
+        self.generation = generation;
+    }
+
+    fn on_speciated(&mut self, _island: usize, speciation: &Speciation<INPUT_SZ, OUTPUT_SZ>) {
+        self.species = speciation.species.len();
+    }
+
+    fn on_evaluated(&mut self, island: usize, evaluation: &Evaluation) {
+        let mut summary = format!(
+            "Generation {} island {}: champion {:.4}, average {:.4}, {} species",
+            self.generation,
+            island,
+            evaluation.champion().1,
+            evaluation.average_fitness(),
+            self.species
+        );
+
+        for name in evaluation.metric_names() {
+            if let Some(average) = evaluation.average_metric(name) {
+                summary += &format!(", average {} {:.4}", name, average);
+            }
+        }
+        if evaluation.invalid_count() > 0 {
+            summary += &format!(", {} invalid", evaluation.invalid_count());
+        }
+
+        println!("{}", summary);
+    }
+
+    fn on_species_extinct(&mut self, island: usize, species: SpeciesId) {
+        println!("Species {} on island {} went extinct", species, island);
+    }
+
+    fn on_new_champion(
+        &mut self,
+        island: usize,
+        genome: &Genome<INPUT_SZ, OUTPUT_SZ>,
+        fitness: f32,
+    ) {
+        println!(
+            "New champion {} on island {} with fitness {:.4}",
+            genome.identifier(),
+            island,
+            fitness
+        );
+    }
+}
+
+/// Writes a report line by line. If a write fails, e.g. because the disk is
+/// full, the error is logged and the report stops rather than the run.
+struct ReportWriter<W: Write> {
+    writer: W,
+    /// What the report is called in the error message.
+    name: &'static str,
+    failed: bool,
+}
+
+impl<W: Write> ReportWriter<W> {
+    fn new(writer: W, name: &'static str) -> Self {
+        Self {
+            writer,
+            name,
+            failed: false,
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.failed {
+            return;
+        }
+
+        if let Err(err) = writeln!(self.writer, "{}", line) {
+            eprintln!("Stopped writing {} report: {}", self.name, err);
+            self.failed = true;
+        }
+    }
+}
+
+/// Writes one row per island and generation, with a column for the average
+/// of each of `metrics`. The columns are fixed up front so every row lines up
+/// with the header, and a metric an evaluation doesn't report is left empty.
+pub struct CsvReporter<W: Write> {
+    writer: ReportWriter<W>,
+    generation: usize,
+    species: usize,
+    metrics: Vec<String>,
+    header_written: bool,
+}
+
+impl<W: Write> CsvReporter<W> {
+    pub fn new(writer: W, metrics: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            writer: ReportWriter::new(writer, "CSV"),
+            generation: 0,
+            species: 0,
+            metrics: metrics.into_iter().map(Into::into).collect(),
+            header_written: false,
+        }
+    }
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize, W: Write> Reporter<INPUT_SZ, OUTPUT_SZ>
+    for CsvReporter<W>
+{
+    fn on_generation_start(&mut self, generation: usize) {
+        self.generation = generation;
+    }
+
+    fn on_speciated(&mut self, _island: usize, speciation: &Speciation<INPUT_SZ, OUTPUT_SZ>) {
+        self.species = speciation.species.len();
+    }
+
+    fn on_evaluated(&mut self, island: usize, evaluation: &Evaluation) {
+        if !self.header_written {
+            let columns = self
+                .metrics
+                .iter()
+                .map(|name| format!(",{}", csv_field(&format!("average_{}", name))))
+                .collect::<String>();
+
+            self.writer.write_line(&format!(
+                "generation,island,species,champion_fitness,average_fitness,invalid{}",
+                columns
+            ));
+            self.header_written = true;
+        }
+
+        let columns = self
+            .metrics
+            .iter()
+            .map(|name| match evaluation.average_metric(name) {
+                Some(average) => format!(",{}", average),
+                None => ",".to_string(),
+            })
+            .collect::<String>();
+
+        self.writer.write_line(&format!(
+            "{},{},{},{},{},{}{}",
+            self.generation,
+            island,
+            self.species,
+            evaluation.champion().1,
+            evaluation.average_fitness(),
+            evaluation.invalid_count(),
+            columns
+        ));
+    }
+}
+
+/// Writes every callback as a JSON object on its own line.
+pub struct JsonlReporter<W: Write> {
+    writer: ReportWriter<W>,
+}
+
+impl<W: Write> JsonlReporter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: ReportWriter::new(writer, "JSONL"),
+        }
+    }
+
+    fn write(&mut self, line: String) {
+        self.writer.write_line(&line);
+    }
+}
+
+impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize, W: Write> Reporter<INPUT_SZ, OUTPUT_SZ>
+    for JsonlReporter<W>
+{
+    fn on_generation_start(&mut self, generation: usize) {
+        self.write(format!(
+            r#"{{"event":"generation_start","generation":{}}}"#,
+            generation
+        ));
+    }
+
+    fn on_speciated(&mut self, island: usize, speciation: &Speciation<INPUT_SZ, OUTPUT_SZ>) {
+        let sizes = speciation
+            .species
+            .values()
+            .map(|species| {
+                format!(
+                    "{}:{}",
+                    json_string(&species.id.to_string()),
+                    species.members.len()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        self.write(format!(
+            r#"{{"event":"speciated","island":{},"species":{{{}}}}}"#,
+            island, sizes
+        ));
+    }
+
+    fn on_evaluated(&mut self, island: usize, evaluation: &Evaluation) {
+        let (champion, champion_fitness) = evaluation.champion();
+        let metrics = evaluation
+            .metric_names()
+            .into_iter()
+            .filter_map(|name| {
+                let average = evaluation.average_metric(name)?;
+                Some(format!("{}:{}", json_string(name), json_number(average)))
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        self.write(format!(
+            r#"{{"event":"evaluated","island":{},"champion":{},"champion_fitness":{},"average_fitness":{},"invalid":{},"average_metrics":{{{}}}}}"#,
+            island,
+            champion,
+            json_number(champion_fitness),
+            json_number(evaluation.average_fitness()),
+            evaluation.invalid_count(),
+            metrics
+        ));
+    }
+
+    fn on_species_extinct(&mut self, island: usize, species: SpeciesId) {
+        self.write(format!(
+            r#"{{"event":"species_extinct","island":{},"species":{}}}"#,
+            island, species
+        ));
+    }
+
+    fn on_new_champion(
+        &mut self,
+        island: usize,
+        genome: &Genome<INPUT_SZ, OUTPUT_SZ>,
+        fitness: f32,
+    ) {
+        self.write(format!(
+            r#"{{"event":"new_champion","island":{},"fitness":{},"genome":{}}}"#,
+            island,
+            json_number(fitness),
+            json_string(&genome.to_string())
+        ));
+    }
+}
+
+/// Quotes `value` if it contains anything that would split or end the field.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A quoted JSON string with quotes, backslashes and control characters
+/// escaped.
+fn json_string(value: &str) -> String {
+    let mut json = String::from('"');
+
+    for char in value.chars() {
+        match char {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            '\r' => json += "\\r",
+            '\t' => json += "\\t",
+            char if char.is_control() => json += &format!("\\u{:04x}", char as u32),
+            char => json.push(char),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+/// JSON has no representation for NaN or infinities.
+fn json_number(value: f32) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::EvaluationResult;
+    use std::io;
+
+    /// Fails every write, like a full disk or a closed pipe.
+    #[derive(Default)]
+    struct Broken {
+        attempts: usize,
+    }
+
+    impl Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            self.attempts += 1;
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_errors_stop_the_report() {
+        let mut jsonl = JsonlReporter::new(Broken::default());
+        Reporter::<2, 1>::on_generation_start(&mut jsonl, 1);
+        Reporter::<2, 1>::on_generation_start(&mut jsonl, 2);
+
+        assert!(jsonl.writer.failed);
+        assert_eq!(jsonl.writer.writer.attempts, 1);
+    }
+
+    #[test]
+    fn test_csv_and_jsonl() {
+        let evaluation = Evaluation::new(
+            [
+                (0, EvaluationResult::new(0.5).with_metric("accuracy", 0.5)),
+                (1, EvaluationResult::new(1.5).with_metric("accuracy", 1.0)),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        let mut csv = CsvReporter::new(Vec::new(), ["accuracy", "steps, taken"]);
+        Reporter::<2, 1>::on_generation_start(&mut csv, 3);
+        Reporter::<2, 1>::on_evaluated(&mut csv, 1, &evaluation);
+        Reporter::<2, 1>::on_evaluated(
+            &mut csv,
+            2,
+            &Evaluation::new([(0, 2.0.into())].into_iter().collect()),
+        );
+        Reporter::<2, 1>::on_evaluated(
+            &mut csv,
+            3,
+            &Evaluation::new(
+                [(
+                    0,
+                    EvaluationResult::new(1.0).with_metric("steps, taken", 4.0),
+                )]
+                .into_iter()
+                .collect(),
+            ),
+        );
+        assert_eq!(
+            String::from_utf8(csv.writer.writer).unwrap(),
+            concat!(
+                "generation,island,species,champion_fitness,average_fitness,invalid,average_accuracy,\"average_steps, taken\"\n",
+                "3,1,0,1.5,1,0,0.75,\n",
+                "3,2,0,2,2,0,,\n",
+                "3,3,0,1,1,0,,4\n"
+            )
+        );
+
+        let mut jsonl = JsonlReporter::new(Vec::new());
+        Reporter::<2, 1>::on_evaluated(&mut jsonl, 0, &evaluation);
+        jsonl.on_new_champion(0, &Genome::<2, 1>::new(), f32::NAN);
+        assert_eq!(
+            String::from_utf8(jsonl.writer.writer).unwrap(),
+            concat!(
+                r#"{"event":"evaluated","island":0,"champion":1,"champion_fitness":1.5,"average_fitness":1,"invalid":0,"average_metrics":{"accuracy":0.75}}"#,
+                "\n",
+                r#"{"event":"new_champion","island":0,"fitness":null,"genome":"0"}"#,
+                "\n"
+            )
+        );
+
+        assert_eq!(
+            json_string("say \"hi\"\\\n\u{1}"),
+            r#""say \"hi\"\\\n\u0001""#
+        );
+    }
+}
@@ -1,6 +1,4 @@
-use crate::{client::ClientId, evaluation::Evaluation, genome::Genome};
-
-const COMPATIBILITY_THRESHOLD: f32 = 6.0;
+use crate::{client::ClientId, config::NeatConfig, evaluation::Evaluation, genome::Genome};
 
 pub type SpeciesId = usize;
 
@@ -15,8 +13,8 @@ pub struct Species<const INPUT_SZ: usize, const OUTPUT_SZ: usize> {
 }
 
 impl<const INPUT_SZ: usize, const OUTPUT_SZ: usize> Species<INPUT_SZ, OUTPUT_SZ> {
-    pub fn is_compatible(&self, genome: &Genome<INPUT_SZ, OUTPUT_SZ>) -> bool {
-        self.representative.distance(genome) < COMPATIBILITY_THRESHOLD
+    pub fn is_compatible(&self, genome: &Genome<INPUT_SZ, OUTPUT_SZ>, config: &NeatConfig) -> bool {
+        self.representative.distance(genome) < config.compatibility_threshold
     }
 
     pub fn sort_by_fitness(&mut self, evaluation: &Evaluation) {
@@ -0,0 +1,180 @@
+use eframe::{
+    egui::{
+        self,
+        plot::{Plot, PlotPoints, Polygon, VLine},
+    },
+    epaint::{Color32, Hsva, Vec2},
+};
+
+use crate::{speciation::Speciation, species::SpeciesId};
+
+/// The same species always gets the same color, and consecutive IDs get
+/// colors far apart.
+pub fn species_color(species: SpeciesId) -> Color32 {
+    const GOLDEN_RATIO_CONJUGATE: f32 = 0.618_034;
+
+    let hue = (species as f32 * GOLDEN_RATIO_CONJUGATE).fract();
+    Hsva::new(hue, 0.6, 0.85, 1.0).into()
+}
+
+/// The `(species, bottom, top)` of every band in one generation, stacked
+/// from the oldest species at the bottom.
+type Stack = Vec<(SpeciesId, f64, f64)>;
+
+fn stack(sizes: &[(SpeciesId, usize)]) -> Stack {
+    let mut sizes = sizes.to_vec();
+    sizes.sort_unstable_by_key(|(species, _)| *species);
+
+    let mut bottom = 0.0;
+    sizes
+        .into_iter()
+        .map(|(species, size)| {
+            let top = bottom + size as f64;
+            let band = (species, bottom, top);
+            bottom = top;
+            band
+        })
+        .collect()
+}
+
+/// Where `species` is in `stack`, or the empty band it would be if it isn't
+/// in it, so bands taper off instead of jumping when a species appears or
+/// goes extinct.
+fn band(stack: &Stack, species: SpeciesId) -> (f64, f64) {
+    let mut bottom = 0.0;
+
+    for &(other, other_bottom, other_top) in stack {
+        if other == species {
+            return (other_bottom, other_top);
+        }
+        if other > species {
+            break;
+        }
+        bottom = other_top;
+    }
+
+    (bottom, bottom)
+}
+
+/// The generation and species under `(x, y)`.
+fn species_at(stacks: &[Stack], x: f64, y: f64) -> Option<(usize, SpeciesId)> {
+    let generation = x.round().max(0.0) as usize;
+
+    stacks
+        .get(generation)?
+        .iter()
+        .find(|(_, bottom, top)| (*bottom..*top).contains(&y))
+        .map(|(species, _, _)| (generation, *species))
+}
+
+/// The classic NEAT speciation plot: species sizes stacked on top of each
+/// other for every generation.
+pub struct SpeciesChart {
+    stacks: Vec<Stack>,
+    /// Generations to draw a vertical line at.
+    markers: Vec<f64>,
+}
+
+impl SpeciesChart {
+    pub fn new<'a, const INPUT_SZ: usize, const OUTPUT_SZ: usize>(
+        speciations: impl Iterator<Item = &'a Speciation<INPUT_SZ, OUTPUT_SZ>>,
+    ) -> Self {
+        Self {
+            stacks: speciations
+                .map(|speciation| {
+                    stack(
+                        &speciation
+                            .species
+                            .values()
+                            .map(|species| (species.id, species.members.len()))
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .collect(),
+            markers: Vec::new(),
+        }
+    }
+
+    pub fn markers(mut self, markers: Vec<f64>) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    /// Returns the generation and species of the band that was clicked.
+    pub fn show(&self, ui: &mut egui::Ui) -> Option<(usize, SpeciesId)> {
+        let stacks = self.stacks.clone();
+
+        Plot::new("species_chart")
+            .clamp_grid(true)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .set_margin_fraction(Vec2::splat(0.1))
+            .label_formatter(move |_, value| {
+                species_at(&stacks, value.x, value.y)
+                    .map(|(_, species)| format!("Species {}", species))
+                    .unwrap_or_default()
+            })
+            .view_aspect(2.0)
+            .show(ui, |plot_ui| {
+                // Each band is drawn as one quad per generation, since plot
+                // polygons have to be convex.
+                for (generation, pair) in self.stacks.windows(2).enumerate() {
+                    let mut species = pair
+                        .iter()
+                        .flatten()
+                        .map(|(species, _, _)| *species)
+                        .collect::<Vec<_>>();
+                    species.sort_unstable();
+                    species.dedup();
+
+                    for species in species {
+                        let (from_bottom, from_top) = band(&pair[0], species);
+                        let (to_bottom, to_top) = band(&pair[1], species);
+                        let x = generation as f64;
+
+                        plot_ui.polygon(
+                            Polygon::new(PlotPoints::new(vec![
+                                [x, from_bottom],
+                                [x + 1.0, to_bottom],
+                                [x + 1.0, to_top],
+                                [x, from_top],
+                            ]))
+                            .color(species_color(species))
+                            .fill_alpha(1.0)
+                            .width(0.0),
+                        );
+                    }
+                }
+
+                for &marker in &self.markers {
+                    plot_ui.vline(VLine::new(marker).color(Color32::GRAY));
+                }
+
+                if plot_ui.plot_clicked() {
+                    plot_ui
+                        .pointer_coordinate()
+                        .and_then(|pointer| species_at(&self.stacks, pointer.x, pointer.y))
+                } else {
+                    None
+                }
+            })
+            .inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack() {
+        let stack = stack(&[(4, 2), (1, 3), (7, 5)]);
+
+        assert_eq!(stack, vec![(1, 0.0, 3.0), (4, 3.0, 5.0), (7, 5.0, 10.0)]);
+        assert_eq!(band(&stack, 4), (3.0, 5.0));
+        assert_eq!(band(&stack, 5), (5.0, 5.0));
+        assert_eq!(band(&stack, 0), (0.0, 0.0));
+        assert_eq!(band(&stack, 9), (10.0, 10.0));
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{
+    environment::{Environment, TestCase, TestCases},
+    evaluation::EvaluationResult,
+    genome::Genome,
+};
+
+pub const DATA: [([f32; 2], [f32; 1]); 4] = [
+    ([0.0, 0.0], [0.0]),
+    ([1.0, 0.0], [1.0]),
+    ([0.0, 1.0], [1.0]),
+    ([1.0, 1.0], [0.0]),
+];
+
+pub struct XOREnv;
+
+/// The fitness is 1 minus the root mean squared error, so each case takes
+/// its share of that by how much of the squared error it makes up.
+fn fitness_contributions(errors: &[f32]) -> Vec<f32> {
+    let total = errors.iter().sum::<f32>();
+    let lost = (total / errors.len() as f32).sqrt();
+
+    errors
+        .iter()
+        .map(|error| {
+            if total > 0.0 {
+                -lost * error / total
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+impl Environment<2, 1> for XOREnv {
+    // fn evaluate(&mut self, genome: &mut Genome<2, 1>) -> f32 {
+    //     let mut fitness = 4.0;
+    //
+    //     for (input, output) in DATA {
+    //         let diff = genome.activate::<[f32; 2], [f32; 1]>(input)[0] - output[0];
+    //         fitness -= diff * diff;
+    //     }
+    //
+    //     fitness
+    // }
+    fn evaluate(&mut self, genome: &mut Genome<2, 1>) -> EvaluationResult {
+        let mut error = 0.0;
+        let mut correct = 0;
+
+        for (input, output) in DATA {
+            let actual = genome.activate::<[f32; 2]>(input).output[0];
+            let diff = actual - output[0];
+            error += diff * diff;
+
+            if actual.round() == output[0] {
+                correct += 1;
+            }
+        }
+
+        EvaluationResult::new(1.0 - (error / DATA.len() as f32).sqrt())
+            .with_metric("accuracy", correct as f32 / DATA.len() as f32)
+    }
+
+    fn training_points(&self) -> Vec<([f32; 2], [f32; 1])> {
+        DATA.to_vec()
+    }
+
+    fn test_cases(&self) -> TestCases<2, 1> {
+        TestCases {
+            cases: self
+                .training_points()
+                .into_iter()
+                .map(|(input, expected)| {
+                    TestCase::new(format!("{} XOR {}", input[0], input[1]), input, expected)
+                })
+                .collect(),
+            fitness_contributions: Some(fitness_contributions),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fitness_contributions() {
+        // Without connections every case gets the same output
+        let mut genome = Genome::<2, 1>::new();
+        let test_cases = XOREnv.test_cases();
+        let errors = test_cases
+            .cases
+            .iter()
+            .map(|case| case.error(&genome.activate(case.input).output))
+            .collect::<Vec<_>>();
+        let contributions = (test_cases.fitness_contributions.unwrap())(&errors);
+
+        assert_eq!(test_cases.cases[1].name, "1 XOR 0");
+        assert!(
+            (1.0 + contributions.iter().sum::<f32>() - XOREnv.evaluate(&mut genome).fitness).abs()
+                < 1e-5
+        );
+        assert_eq!(fitness_contributions(&[0.0; 4]), vec![0.0; 4]);
+    }
+}
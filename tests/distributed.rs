@@ -0,0 +1,105 @@
+use std::{
+    net::TcpStream,
+    process::{Child, Command},
+    thread,
+    time::{Duration, Instant},
+};
+
+use ai_world::{
+    distributed::Distributed, environment::PopulationEnvironment,
+    innovation_record::InnovationRecord, lineage::Lineage, population::Population, xor::XOREnv,
+};
+use rand::thread_rng;
+
+/// A worker process that is killed when the test ends, however it ends.
+struct Worker(Child);
+
+impl Worker {
+    fn kill(&mut self) {
+        // It may have exited already
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+fn spawn_worker(coordinator: &Distributed) -> Worker {
+    Worker(
+        Command::new(env!("CARGO_BIN_EXE_worker"))
+            .arg(coordinator.local_addr().to_string())
+            .spawn()
+            .expect("Failed to start worker"),
+    )
+}
+
+/// Workers can take a while to start, and a coordinator with a short timeout
+/// gives up on them if none are connected.
+fn wait_for_workers(coordinator: &Distributed, workers: usize) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+
+    while coordinator.workers() < workers {
+        assert!(Instant::now() < deadline, "Workers didn't connect");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn population() -> Population<2, 1> {
+    Population::new(
+        &mut thread_rng(),
+        &mut InnovationRecord::default(),
+        &mut Lineage::default(),
+        50,
+    )
+}
+
+#[test]
+fn test_matches_local_evaluation() {
+    let mut coordinator = Distributed::bind("127.0.0.1:0", Duration::from_secs(5)).unwrap();
+    let mut workers = [spawn_worker(&coordinator), spawn_worker(&coordinator)];
+
+    let mut population = population();
+    let remote = coordinator.evaluate_population(&mut population);
+    let local = population.evaluate(&mut XOREnv);
+    assert_eq!(remote.results, local.results);
+
+    // The remaining worker picks up everything once one of them dies.
+    workers[0].kill();
+
+    let remote = coordinator.evaluate_population(&mut population);
+    assert_eq!(remote.fitness, local.fitness);
+}
+
+#[test]
+fn test_reassigns_after_timeout() {
+    let mut coordinator = Distributed::bind("127.0.0.1:0", Duration::from_millis(200)).unwrap();
+
+    // Accepts a genome but never answers.
+    let _unresponsive = TcpStream::connect(coordinator.local_addr()).unwrap();
+    let _worker = spawn_worker(&coordinator);
+    wait_for_workers(&coordinator, 2);
+
+    let mut population = population();
+    let remote = coordinator.evaluate_population(&mut population);
+    assert_eq!(remote.fitness, population.evaluate(&mut XOREnv).fitness);
+}
+
+#[test]
+fn test_gives_up_without_workers() {
+    let mut coordinator = Distributed::bind("127.0.0.1:0", Duration::from_millis(100)).unwrap();
+
+    let mut population = population();
+    let remote = coordinator.evaluate_population(&mut population);
+    assert_eq!(remote.results.len(), population.members.len());
+    assert_eq!(remote.invalid_count(), population.members.len());
+
+    // Nothing is left over for a worker that connects later
+    let _worker = spawn_worker(&coordinator);
+    wait_for_workers(&coordinator, 1);
+    let remote = coordinator.evaluate_population(&mut population);
+    assert_eq!(remote.fitness, population.evaluate(&mut XOREnv).fitness);
+}